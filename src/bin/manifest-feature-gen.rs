@@ -0,0 +1,317 @@
+//! Run feature generation outside build scripts(pre-commit hooks, release tooling)
+//!
+//! ```text
+//! manifest-feature-gen <generate|verify|diff|report|watch> [--manifest-path <path>] (--spec <file> | --generator <dir>) [--check] [--update-lock] [--watch <path>...]
+//! ```
+//!
+//! Spec file is TOML of `name = ["dependency", ...]` entries, loaded with `read_feature_definitions`.\
+//! Generator crate is run with `cargo run`, while `MANIFEST_FEATURE_GEN_MANIFEST` points the manifest.\
+//! `watch` polls modification time of manifest, spec file or generator crate and additional `--watch` paths,
+//! then regenerates on change.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+    time::{Duration, SystemTime},
+};
+
+use manifest_feature_gen::{
+    read_feature_definitions, Error, FeatureDiff, GenerationPolicy, GenerationReport, Manifest,
+    MANIFEST_OVERRIDE_ENV,
+};
+
+const USAGE: &str = "\
+Usage: manifest-feature-gen <COMMAND> [OPTIONS]
+
+Commands:
+  generate  Generate features and write manifest
+  verify    Check whether generated features are up to date
+  diff      Print changes of [features] table generation would make
+  report    Print generation report(spec only)
+  watch     Regenerate whenever inputs change
+
+Options:
+  --manifest-path <PATH>  Manifest to generate features of [default: Cargo.toml]
+  --spec <FILE>           Feature definitions to add
+  --generator <DIR>       Crate generating features against MANIFEST_FEATURE_GEN_MANIFEST
+  --check                 Exit with failure when generated features are outdated(verify only)
+  --update-lock           Write feature lockfile of generated features(generate and watch, spec only)
+  --watch <PATH>          Additional file or directory to watch, e.g. scanned directories(watch only)
+  --interval <MS>         Polling interval in milliseconds [default: 500](watch only)
+  -h, --help              Print help";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Subcommand {
+    Generate,
+    Verify,
+    Diff,
+    Report,
+    Watch,
+}
+
+#[derive(Debug)]
+enum Input {
+    Spec(PathBuf),
+    Generator(PathBuf),
+}
+
+#[derive(Debug)]
+struct Args {
+    command: Subcommand,
+    manifest_path: PathBuf,
+    input: Input,
+    check: bool,
+    update_lock: bool,
+    watch: Vec<PathBuf>,
+    interval: Duration,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let command = match args.next().as_deref() {
+        Some("generate") => Subcommand::Generate,
+        Some("verify") => Subcommand::Verify,
+        Some("diff") => Subcommand::Diff,
+        Some("report") => Subcommand::Report,
+        Some("watch") => Subcommand::Watch,
+        Some(command) => return Err(format!("unknown command - {}", command)),
+        None => return Err("command is required".to_string()),
+    };
+
+    let mut manifest_path = None;
+    let mut input = None;
+    let mut check = false;
+    let mut update_lock = false;
+    let mut watch = Vec::new();
+    let mut interval = Duration::from_millis(500);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} requires value", name))
+        };
+        match arg.as_str() {
+            "--manifest-path" => manifest_path = Some(PathBuf::from(value(&arg)?)),
+            "--spec" => input = Some(Input::Spec(PathBuf::from(value(&arg)?))),
+            "--generator" => input = Some(Input::Generator(PathBuf::from(value(&arg)?))),
+            "--check" => check = true,
+            "--update-lock" => update_lock = true,
+            "--watch" => watch.push(PathBuf::from(value(&arg)?)),
+            "--interval" => {
+                let value = value(&arg)?;
+                let millis = value
+                    .parse()
+                    .map_err(|_| format!("invalid interval - {}", value))?;
+                interval = Duration::from_millis(millis);
+            }
+            _ => return Err(format!("unknown argument - {}", arg)),
+        }
+    }
+
+    let input = input.ok_or("either --spec or --generator is required")?;
+    if update_lock && matches!(input, Input::Generator(_)) {
+        return Err("--update-lock is supported with --spec only".to_string());
+    }
+
+    Ok(Args {
+        command,
+        manifest_path: manifest_path.unwrap_or_else(|| PathBuf::from("Cargo.toml")),
+        input,
+        check,
+        update_lock,
+        watch,
+        interval,
+    })
+}
+
+/// Load manifest and add features of spec file
+fn load_spec(manifest_path: &Path, spec: &Path) -> Result<Manifest, Error> {
+    let mut manifest = Manifest::new_with_override(Some(manifest_path.to_path_buf()), false)?;
+    manifest.add_feature_definitions(read_feature_definitions(spec)?)?;
+    Ok(manifest)
+}
+
+/// Report of spec without writing manifest
+fn spec_report(manifest_path: &Path, spec: &Path) -> Result<GenerationReport, Error> {
+    let mut manifest = load_spec(manifest_path, spec)?;
+    manifest.set_generation_policy(GenerationPolicy::Skip);
+    manifest.write_with_report(&Default::default())
+}
+
+fn run_generator(manifest_path: &Path, generator: &Path) -> Result<(), Error> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = Command::new(cargo)
+        .arg("run")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(generator.join("Cargo.toml"))
+        .env(MANIFEST_OVERRIDE_ENV, manifest_path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("generator failed - {}", status)).into())
+    }
+}
+
+/// Changes generator would make. Manifest is restored after running generator.
+fn generator_diff(manifest_path: &Path, generator: &Path) -> Result<FeatureDiff, Error> {
+    let mut manifest = Manifest::new_with_override(Some(manifest_path.to_path_buf()), false)?;
+    let original = std::fs::read(manifest.path())?;
+    let generated = run_generator(manifest.path(), generator)
+        .and_then(|_| Ok(std::fs::read_to_string(manifest.path())?));
+    std::fs::write(manifest.path(), original)?;
+    *manifest.document_mut() = generated?.parse()?;
+    manifest.feature_diff()
+}
+
+fn generate(args: &Args) -> Result<(), Error> {
+    match &args.input {
+        Input::Spec(spec) => {
+            let manifest = load_spec(&args.manifest_path, spec)?;
+            if args.update_lock && manifest.update_lock()? {
+                println!("lockfile updated - {}", manifest.lock_path().display());
+            }
+            let report = manifest.write_with_report(&Default::default())?;
+            for line in report.summary() {
+                println!("{}", line);
+            }
+            if !report.written {
+                println!("generated features are up to date");
+            }
+            Ok(())
+        }
+        Input::Generator(generator) => run_generator(&args.manifest_path, generator),
+    }
+}
+
+/// Collect modification time of files under `path`. `target` directories are skipped.
+fn collect_mtimes(path: &Path, mtimes: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+    if path.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        let mut entries = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.file_name().is_some_and(|name| name != "target"))
+            .collect::<Vec<_>>();
+        entries.sort();
+        for entry in entries {
+            collect_mtimes(&entry, mtimes);
+        }
+    } else {
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        mtimes.push((path.to_path_buf(), modified));
+    }
+}
+
+/// Fingerprint of watched inputs
+fn fingerprint(args: &Args) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let input = match &args.input {
+        Input::Spec(spec) => spec,
+        Input::Generator(generator) => generator,
+    };
+    let mut mtimes = Vec::new();
+    for path in [&args.manifest_path, input].into_iter().chain(&args.watch) {
+        collect_mtimes(path, &mut mtimes);
+    }
+    mtimes
+}
+
+fn watch(args: &Args) -> ! {
+    let mut last = None;
+    loop {
+        let current = fingerprint(args);
+        if last.as_ref() != Some(&current) {
+            if let Err(e) = generate(args) {
+                eprintln!("error: {}", e);
+            }
+            // manifest written by generation is not a change of inputs
+            last = Some(fingerprint(args));
+        }
+        std::thread::sleep(args.interval);
+    }
+}
+
+fn run(args: Args) -> Result<ExitCode, Error> {
+    match (args.command, &args.input) {
+        (Subcommand::Generate, _) => generate(&args)?,
+        (Subcommand::Watch, _) => watch(&args),
+        (Subcommand::Verify, input) => {
+            let outdated = match input {
+                Input::Spec(spec) => {
+                    let report = spec_report(&args.manifest_path, spec)?;
+                    for line in report.summary() {
+                        println!("{}", line);
+                    }
+                    report.is_changed()
+                }
+                Input::Generator(generator) => {
+                    let diff = generator_diff(&args.manifest_path, generator)?;
+                    print!("{}", diff);
+                    !diff.is_empty()
+                }
+            };
+            if !outdated {
+                println!("generated features are up to date");
+            } else if args.check {
+                eprintln!("generated features are outdated");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        (Subcommand::Diff, Input::Spec(spec)) => {
+            print!("{}", load_spec(&args.manifest_path, spec)?.feature_diff()?);
+        }
+        (Subcommand::Diff, Input::Generator(generator)) => {
+            print!("{}", generator_diff(&args.manifest_path, generator)?);
+        }
+        (Subcommand::Report, Input::Spec(spec)) => {
+            let report = spec_report(&args.manifest_path, spec)?;
+            for line in report.summary() {
+                println!("{}", line);
+            }
+            for (group, chosen) in &report.chosen {
+                println!("group '{}': {}", group, chosen.join(", "));
+            }
+            for (feature, rust_version) in &report.rust_versions {
+                println!("feature({}) requires rust {}", feature, rust_version);
+            }
+            for (feature, replacement) in &report.deprecated {
+                println!(
+                    "feature({}) is deprecated. Please use {}",
+                    feature, replacement
+                );
+            }
+            for hazard in &report.unification_hazards {
+                print!("{}", hazard);
+            }
+        }
+        (Subcommand::Report, Input::Generator(_)) => {
+            eprintln!("report is supported with --spec only");
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let args = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
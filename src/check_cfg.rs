@@ -0,0 +1,90 @@
+use toml_edit::{Array, InlineTable, Item, Table, Value};
+
+use crate::Manifest;
+
+const LINTS_TABLE_NAME: &str = "lints";
+const RUST_LINTS_TABLE_NAME: &str = "rust";
+const UNEXPECTED_CFGS_LINT: &str = "unexpected_cfgs";
+const CHECK_CFG_KEY: &str = "check-cfg";
+
+/// Name of cfg declared by check-cfg spec(e.g. `backend` of `cfg(backend, values("gl"))`)
+fn cfg_name(spec: &str) -> Option<&str> {
+    let inner = spec.trim().strip_prefix("cfg(")?;
+    let end = inner.find([',', ')'])?;
+    Some(inner[..end].trim())
+}
+
+impl Manifest {
+    /// Maintain `check-cfg` of `[lints.rust.unexpected_cfgs]` for cfgs of registered exports, while writing.\
+    /// Entries of other cfgs are kept, so stable check-cfg accepts generated cfgs even without build script directives.
+    pub fn set_maintain_check_cfg(&mut self, maintain: bool) {
+        self.maintain_check_cfg = maintain;
+    }
+
+    /// Update `check-cfg` entries of exported cfgs. Entries are untouched when already in sync.
+    pub(crate) fn update_check_cfg(&mut self) {
+        let generated = self
+            .exports
+            .iter()
+            .flat_map(|export| export.check_cfgs(|group| self.export_group_members(group)))
+            .collect::<Vec<_>>();
+        if generated.is_empty() {
+            return;
+        }
+
+        let lints = self.document[LINTS_TABLE_NAME].or_insert({
+            let mut lints = Table::new();
+            lints.set_implicit(true);
+            Item::Table(lints)
+        });
+        if lints.get("workspace").and_then(Item::as_bool) == Some(true) {
+            println!(
+                "cargo:warning=check-cfg of generated cfgs is not maintained, because lints are inherited from workspace"
+            );
+            return;
+        }
+        let Some(rust) = lints[RUST_LINTS_TABLE_NAME]
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+        else {
+            return;
+        };
+
+        let lint = rust
+            .entry(UNEXPECTED_CFGS_LINT)
+            .or_insert(Item::Value("warn".into()));
+        if let Some(level) = lint.as_str().map(str::to_string) {
+            let mut table = InlineTable::new();
+            table.insert("level", level.into());
+            *lint = Item::Value(Value::InlineTable(table));
+        }
+        let Some(lint) = lint.as_table_like_mut() else {
+            return;
+        };
+
+        let current = lint
+            .get(CHECK_CFG_KEY)
+            .and_then(Item::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let expected = current
+            .iter()
+            .filter(|spec| {
+                !generated
+                    .iter()
+                    .any(|(name, _)| cfg_name(spec) == Some(name.as_str()))
+            })
+            .cloned()
+            .chain(generated.iter().map(|(_, spec)| spec.clone()))
+            .collect::<Vec<_>>();
+        if expected != current {
+            lint.insert(
+                CHECK_CFG_KEY,
+                Item::Value(Value::Array(Array::from_iter(expected))),
+            );
+        }
+    }
+}
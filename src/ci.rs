@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use crate::{json, Error, Manifest};
+
+impl Manifest {
+    /// Render generated groups into cargo-hack arguments.
+    ///
+    /// Each mutually exclusive group is passed with `--mutually-exclusive-features`,
+    /// and children of each parent feature and members of each tier with `--group-features`.
+    /// Group enabling multiple features of an exclusion group is never valid, so it is not passed.
+    pub fn cargo_hack_args(&self) -> Vec<String> {
+        let mut args = vec!["--feature-powerset".to_string()];
+        let exclusion_groups = self.all_exclusion_groups().collect::<Vec<_>>();
+        for group in &exclusion_groups {
+            if group.len() > 1 {
+                args.push("--mutually-exclusive-features".to_string());
+                args.push(group.join(","));
+            }
+        }
+        let groups = self
+            .parent_features
+            .values()
+            .cloned()
+            .chain(self.tier_entries().into_iter().map(|(_, members)| members));
+        let mut grouped = Vec::new();
+        for group in groups {
+            let is_exclusive = exclusion_groups.iter().any(|exclusion_group| {
+                group
+                    .iter()
+                    .filter(|feature| exclusion_group.contains(feature))
+                    .count()
+                    > 1
+            });
+            let group = group.join(",");
+            if !is_exclusive && group.contains(',') && !grouped.contains(&group) {
+                args.push("--group-features".to_string());
+                args.push(group.clone());
+                grouped.push(group);
+            }
+        }
+        args
+    }
+}
+
+impl Manifest {
+    /// Render valid feature combinations as JSON array for CI matrix include block.
+    ///
+    /// Each entry is `{"features": "a,b"}`, usable as `--features ${{ matrix.features }}`.
+    pub fn feature_matrix_json(&self) -> String {
+        let entries = self
+            .feature_combinations()
+            .map(|features| format!("{{\"features\":{}}}", json::string(&features.join(","))))
+            .collect::<Vec<_>>();
+        format!("[{}]", entries.join(","))
+    }
+
+    /// Write `feature_matrix_json` to `path`
+    pub fn write_feature_matrix_json(&self, path: &Path) -> Result<(), Error> {
+        std::fs::write(path, self.feature_matrix_json())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{Manifest, TierMembership};
+
+    #[test]
+    fn cargo_hack_groups() {
+        let mut manifest = Manifest::from_source(
+            PathBuf::from("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+            false,
+        )
+        .unwrap();
+        manifest.declare_exclusion_group("backend");
+        manifest
+            .add_features_to_exclusion_group("backend", ["gl", "vulkan"].into_iter(), |_, _| {})
+            .unwrap();
+        manifest.declare_parent_feature("codecs", None).unwrap();
+        manifest
+            .add_features_under_parent("codecs", ["png", "jpeg"].into_iter(), |_, _| {})
+            .unwrap();
+        manifest
+            .add_tier("default-backends", TierMembership::NonExclusive)
+            .unwrap();
+        manifest
+            .add_tier("everything", TierMembership::AllGenerated)
+            .unwrap();
+
+        assert_eq!(
+            manifest.cargo_hack_args(),
+            [
+                "--feature-powerset",
+                "--mutually-exclusive-features",
+                "gl,vulkan",
+                "--group-features",
+                "png,jpeg",
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, ToFeatureName};
+
+pub(crate) const HEADER: &str = concat!("// auto-generated by ", env!("CARGO_CRATE_NAME"), "\n");
+
+/// `compile_error!` guards for every conflicting pair of mutually exclusive features
+pub(crate) fn exclusion_guards<'a, I: Iterator<Item = &'a [String]>>(groups: I) -> String {
+    let mut code = HEADER.to_string();
+    for members in groups {
+        for (index, a) in members.iter().enumerate() {
+            for b in &members[(index + 1)..] {
+                writeln!(
+                    code,
+                    "#[cfg(all(feature = {:?}, feature = {:?}))]\ncompile_error!({:?});",
+                    a,
+                    b,
+                    format!(
+                        "Mutually exclusive features are enabled at the same time - {}, {}",
+                        a, b
+                    )
+                )
+                .unwrap();
+            }
+        }
+    }
+    code
+}
+
+/// Enum mirroring `members` of exclusion group, with `CURRENT` constant set to `selected`.\
+/// Each member is given as pair of variant name and feature name.
+pub(crate) fn selection_enum(
+    name: &str,
+    members: &[(String, String)],
+    selected: Option<&str>,
+) -> String {
+    let mut code = HEADER.to_string();
+    writeln!(
+        code,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum {} {{",
+        name
+    )
+    .unwrap();
+    for (variant, feature) in members {
+        writeln!(code, "    /// feature `{}`\n    {},", feature, variant).unwrap();
+    }
+    writeln!(code, "}}\n\nimpl {} {{", name).unwrap();
+    let current = members
+        .iter()
+        .find(|(_, feature)| Some(feature.as_str()) == selected)
+        .map(|(variant, _)| format!("Some({}::{})", name, variant))
+        .unwrap_or_else(|| "None".to_string());
+    writeln!(
+        code,
+        "    /// Variant selected for current build\n    pub const CURRENT: Option<{}> = {};\n",
+        name, current
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "    /// Name of feature\n    pub const fn feature_name(self) -> &'static str {{\n        match self {{"
+    )
+    .unwrap();
+    for (variant, feature) in members {
+        writeln!(code, "            {}::{} => {:?},", name, variant, feature).unwrap();
+    }
+    writeln!(code, "        }}\n    }}\n}}").unwrap();
+    code
+}
+
+/// Write module re-exporting module of `selected` feature as `alias`.\
+/// `module_path` maps feature to its module path(e.g. `crate::backends::gl`).
+///
+/// Written module is empty when nothing is selected.
+pub fn write_dispatcher<T: ToFeatureName, F: Fn(&T) -> String>(
+    path: &Path,
+    alias: &str,
+    selected: Option<&T>,
+    module_path: F,
+) -> Result<(), Error> {
+    let mut code = HEADER.to_string();
+    if let Some(selected) = selected {
+        writeln!(
+            code,
+            "// selected feature: {}\npub use {} as {};",
+            selected.to_feature_name(),
+            module_path(selected),
+            alias
+        )
+        .unwrap();
+    }
+    std::fs::write(path, code)?;
+    Ok(())
+}
+
+/// Same as `write_dispatcher`, but module is written to `OUT_DIR/file_name`
+///
+/// ```ignore
+/// include!(concat!(env!("OUT_DIR"), "/dispatcher.rs"));
+/// ```
+pub fn write_dispatcher_with_env<T: ToFeatureName, F: Fn(&T) -> String>(
+    file_name: &str,
+    alias: &str,
+    selected: Option<&T>,
+    module_path: F,
+) -> Result<PathBuf, Error> {
+    let mut path = crate::env::var_path("OUT_DIR")?;
+    path.push(file_name);
+    write_dispatcher(&path, alias, selected, module_path)?;
+    Ok(path)
+}
@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+
+/// Iterator over valid combinations of generated features.
+///
+/// At most one feature of each mutually exclusive group is enabled in a combination,
+/// and every combination contains all generated features implied by its features.
+pub struct FeatureCombinations {
+    /// Each slot chooses none or one of its features
+    slots: Vec<Vec<String>>,
+    exclusion_groups: Vec<Vec<String>>,
+    implications: HashMap<String, Vec<String>>,
+    counter: Option<Vec<usize>>,
+}
+
+impl FeatureCombinations {
+    pub(crate) fn new(
+        features: &[String],
+        exclusion_groups: Vec<Vec<String>>,
+        implications: HashMap<String, Vec<String>>,
+    ) -> Self {
+        let mut assigned = HashSet::new();
+        let mut slots = Vec::new();
+        for group in &exclusion_groups {
+            let slot = group
+                .iter()
+                .filter(|feature| features.contains(feature) && assigned.insert(*feature))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !slot.is_empty() {
+                slots.push(slot);
+            }
+        }
+        for feature in features {
+            if assigned.insert(feature) {
+                slots.push(vec![feature.clone()]);
+            }
+        }
+
+        let counter = Some(vec![0; slots.len()]);
+        Self {
+            slots,
+            exclusion_groups,
+            implications,
+            counter,
+        }
+    }
+
+    fn advance(&mut self) {
+        if let Some(counter) = &mut self.counter {
+            for (digit, slot) in counter.iter_mut().zip(&self.slots) {
+                *digit += 1;
+                if *digit <= slot.len() {
+                    return;
+                }
+                *digit = 0;
+            }
+            self.counter = None;
+        }
+    }
+
+    fn is_valid(&self, combination: &[String]) -> bool {
+        let enabled = combination.iter().collect::<HashSet<_>>();
+        let implied = combination.iter().all(|feature| {
+            self.implications
+                .get(feature)
+                .map(|implied| implied.iter().all(|implied| enabled.contains(implied)))
+                .unwrap_or(true)
+        });
+        implied
+            && self.exclusion_groups.iter().all(|group| {
+                group
+                    .iter()
+                    .filter(|feature| enabled.contains(feature))
+                    .count()
+                    <= 1
+            })
+    }
+}
+
+impl Iterator for FeatureCombinations {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let counter = self.counter.as_ref()?;
+            let combination = counter
+                .iter()
+                .zip(&self.slots)
+                .filter(|(digit, _)| **digit > 0)
+                .map(|(digit, slot)| slot[digit - 1].clone())
+                .collect::<Vec<_>>();
+            self.advance();
+            if self.is_valid(&combination) {
+                return Some(combination);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn exclusive_features_are_never_combined() {
+        let combinations = FeatureCombinations::new(
+            &names(&["a", "b", "c"]),
+            vec![names(&["a", "b"])],
+            HashMap::new(),
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(combinations.len(), 6);
+        assert!(combinations.contains(&vec![]));
+        assert!(combinations.contains(&names(&["b", "c"])));
+        assert!(!combinations
+            .iter()
+            .any(|combination| combination.contains(&"a".to_string())
+                && combination.contains(&"b".to_string())));
+    }
+
+    #[test]
+    fn implied_features_are_enabled_together() {
+        let combinations = FeatureCombinations::new(
+            &names(&["full", "std"]),
+            Vec::new(),
+            HashMap::from([("full".to_string(), names(&["std"]))]),
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            combinations,
+            [vec![], names(&["std"]), names(&["full", "std"])]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+
+use toml_edit::{Item, Table, TableLike, Value};
+
+use crate::{manifest::FEATURES_TABLE_NAME, Manifest};
+
+pub(crate) const DEPENDENCIES_TABLE_NAME: &str = "dependencies";
+const TARGET_TABLE_NAME: &str = "target";
+
+/// Entries of `[dependencies]` and `[target.*.dependencies]`, which features can refer
+pub(crate) fn dependency_entries_of(document: &Table) -> Vec<(&str, &Item)> {
+    let mut tables = vec![document.get(DEPENDENCIES_TABLE_NAME)];
+    if let Some(targets) = document
+        .get(TARGET_TABLE_NAME)
+        .and_then(Item::as_table_like)
+    {
+        tables.extend(
+            targets
+                .iter()
+                .map(|(_, target)| target.get(DEPENDENCIES_TABLE_NAME)),
+        );
+    }
+
+    tables
+        .into_iter()
+        .flatten()
+        .filter_map(Item::as_table_like)
+        .flat_map(|table| table.iter())
+        .collect()
+}
+
+/// Whether dependency entry is declared with `optional = true`
+pub(crate) fn is_optional_entry(item: &Item) -> bool {
+    item.get("optional").and_then(Item::as_bool) == Some(true)
+}
+
+impl Manifest {
+    /// Entries of `[dependencies]` and `[target.*.dependencies]`, which features can refer
+    pub(crate) fn dependency_entries(&self) -> Vec<(&str, &Item)> {
+        dependency_entries_of(self.document.as_table())
+    }
+
+    /// Mutable `[dependencies]` and `[target.*.dependencies]` tables
+    pub(crate) fn dependency_tables_mut(&mut self) -> Vec<&mut dyn TableLike> {
+        let mut tables = Vec::new();
+        for (key, item) in self.document.as_table_mut().iter_mut() {
+            if key == DEPENDENCIES_TABLE_NAME {
+                tables.extend(item.as_table_like_mut());
+            } else if key == TARGET_TABLE_NAME {
+                if let Some(targets) = item.as_table_like_mut() {
+                    tables.extend(
+                        targets
+                            .iter_mut()
+                            .filter_map(|(_, target)| target.get_mut(DEPENDENCIES_TABLE_NAME))
+                            .filter_map(Item::as_table_like_mut),
+                    );
+                }
+            }
+        }
+        tables
+    }
+
+    /// Find dependency entry by its name in manifest
+    pub(crate) fn dependency_entry(&self, name: &str) -> Option<&Item> {
+        self.dependency_entries()
+            .into_iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, item)| item)
+    }
+
+    /// Names of dependencies referred by features(`dep:name`, `name/feature`, `name?/feature` or `name`)
+    pub(crate) fn referred_dependencies(&self) -> HashSet<String> {
+        self.document[FEATURES_TABLE_NAME]
+            .as_table()
+            .into_iter()
+            .flat_map(|features| features.iter())
+            .filter_map(|(_, deps)| deps.as_array())
+            .flat_map(|deps| deps.iter().filter_map(Value::as_str))
+            .map(|dep| {
+                let dep = dep.strip_prefix("dep:").unwrap_or(dep);
+                let dep = dep.split('/').next().unwrap_or(dep);
+                dep.trim_end_matches('?').to_string()
+            })
+            .collect()
+    }
+
+    /// List optional dependencies not referred by any feature, and remove them when `remove` is set.
+    ///
+    /// Only dependencies made optional by this crate(`set_dependency_optional`, `add_workspace_dependency`)
+    /// are listed, as others may be enabled through their implicit features.
+    /// Ones made optional by previous runs are known only when state is recorded(`set_record_state`).\
+    /// Listed dependencies are returned, so calling with `remove = false` works as dry-run.
+    /// Only optional entries are removed, so non-optional entry of same name(e.g. in `[target.*.dependencies]`) is kept.
+    pub fn gc_optional_dependencies(&mut self, remove: bool) -> Vec<String> {
+        let referred = self.referred_dependencies();
+        let mut unreferred = self
+            .dependency_entries()
+            .into_iter()
+            .filter(|(name, item)| {
+                is_optional_entry(item) && self.optional_dependencies.contains(*name)
+            })
+            .map(|(name, _)| name.to_string())
+            .filter(|name| !referred.contains(name))
+            .collect::<Vec<_>>();
+        unreferred.sort();
+        unreferred.dedup();
+
+        if remove && !unreferred.is_empty() {
+            for table in self.dependency_tables_mut() {
+                for name in &unreferred {
+                    if table.get(name).is_some_and(is_optional_entry) {
+                        table.remove(name);
+                    }
+                }
+            }
+            for name in &unreferred {
+                self.optional_dependencies.remove(name);
+            }
+        }
+
+        unreferred
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::Manifest;
+
+    #[test]
+    fn gc_keeps_non_optional_entries() {
+        let mut manifest = Manifest::from_source(
+            PathBuf::from("Cargo.toml"),
+            r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+log = { version = "0.4", optional = true }
+
+[target.'cfg(windows)'.dependencies]
+log = "0.4"
+"#,
+            false,
+        )
+        .unwrap();
+        manifest.optional_dependencies.insert("log".to_string());
+
+        assert_eq!(manifest.gc_optional_dependencies(true), ["log"]);
+        let document = manifest.document();
+        assert!(document["dependencies"].get("log").is_none());
+        assert!(document["target"]["cfg(windows)"]["dependencies"]
+            .get("log")
+            .is_some());
+    }
+}
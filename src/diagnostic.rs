@@ -0,0 +1,107 @@
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+
+use crate::Error;
+
+/// Rich diagnostic of `Error`, pointing into manifest when location is known.
+///
+/// Render with any `miette` report handler(e.g. `miette::Report::new(diagnostic)`).
+#[derive(Debug)]
+pub struct ManifestDiagnostic {
+    message: String,
+    source_code: Option<NamedSource<String>>,
+    span: Option<(usize, usize)>,
+    label: Option<String>,
+}
+
+impl std::fmt::Display for ManifestDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ManifestDiagnostic {}
+
+impl Diagnostic for ManifestDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(env!("CARGO_PKG_NAME")))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source_code
+            .as_ref()
+            .map(|source| source as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (offset, length) = self.span?;
+        Some(Box::new(std::iter::once(LabeledSpan::new(
+            self.label.clone(),
+            offset,
+            length,
+        ))))
+    }
+}
+
+/// Byte offset of 1-based line and column(in characters)
+fn offset_of(source: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        source.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let column_offset = source[line_start..]
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map(|(offset, _)| offset)?;
+    Some(line_start + column_offset)
+}
+
+impl Error {
+    /// Convert into rich diagnostic. Manifest is read again to annotate location when known, unless its content is kept.
+    pub fn to_diagnostic(&self) -> ManifestDiagnostic {
+        let mut diagnostic = ManifestDiagnostic {
+            message: self.to_string(),
+            source_code: None,
+            span: None,
+            label: None,
+        };
+
+        if let Error::MalformedManifest {
+            path,
+            expected,
+            found,
+            location: Some(location),
+            ..
+        } = self
+        {
+            if let Ok(source) = std::fs::read_to_string(path) {
+                if let Some(offset) = offset_of(&source, location.line, location.column) {
+                    let length = location.snippet.trim_end_matches("...").len().max(1);
+                    diagnostic.span = Some((offset, length));
+                    diagnostic.label = Some(format!("expected {}, found {}", expected, found));
+                    diagnostic.source_code =
+                        Some(NamedSource::new(path.display().to_string(), source));
+                }
+            }
+        }
+
+        if let Error::ManifestParseError {
+            path,
+            source,
+            content,
+        } = self
+        {
+            let code = match content {
+                Some(content) => Ok(content.clone()),
+                None => std::fs::read_to_string(path),
+            };
+            if let (Ok(code), Some(span)) = (code, source.span()) {
+                diagnostic.span = Some((span.start, span.len().max(1)));
+                diagnostic.label = Some(source.message().to_string());
+                diagnostic.source_code = Some(NamedSource::new(path.display().to_string(), code));
+            }
+        }
+
+        diagnostic
+    }
+}
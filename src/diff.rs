@@ -0,0 +1,143 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{Error, Manifest};
+
+/// Change of single feature entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiffEntry {
+    Added {
+        name: String,
+        dependencies: Vec<String>,
+    },
+    Removed {
+        name: String,
+        dependencies: Vec<String>,
+    },
+    Updated {
+        name: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+}
+
+/// Changes of `[features]` table since manifest is loaded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureDiff {
+    pub entries: Vec<DiffEntry>,
+}
+
+fn sorted(dependencies: &HashSet<String>) -> Vec<String> {
+    let mut dependencies = dependencies.iter().cloned().collect::<Vec<_>>();
+    dependencies.sort();
+    dependencies
+}
+
+fn format_array(dependencies: &[String]) -> String {
+    format!(
+        "[{}]",
+        dependencies
+            .iter()
+            .map(|dep| crate::json::string(dep))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[cfg(feature = "color")]
+const GREEN: &str = "\x1b[32m";
+#[cfg(feature = "color")]
+const RED: &str = "\x1b[31m";
+#[cfg(feature = "color")]
+const YELLOW: &str = "\x1b[33m";
+#[cfg(feature = "color")]
+const RESET: &str = "\x1b[0m";
+
+impl DiffEntry {
+    fn lines(&self) -> Vec<(char, String)> {
+        match self {
+            DiffEntry::Added { name, dependencies } => {
+                vec![('+', format!("{} = {}", name, format_array(dependencies)))]
+            }
+            DiffEntry::Removed { name, dependencies } => {
+                vec![('-', format!("{} = {}", name, format_array(dependencies)))]
+            }
+            DiffEntry::Updated { name, old, new } => vec![
+                ('-', format!("{} = {}", name, format_array(old))),
+                ('+', format!("{} = {}", name, format_array(new))),
+            ],
+        }
+    }
+}
+
+impl FeatureDiff {
+    /// Whether nothing is changed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Render with ANSI colors. Added entries are green, removed ones are red and updated ones are yellow.
+    #[cfg(feature = "color")]
+    pub fn colored(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let color = match entry {
+                    DiffEntry::Added { .. } => GREEN,
+                    DiffEntry::Removed { .. } => RED,
+                    DiffEntry::Updated { .. } => YELLOW,
+                };
+                entry
+                    .lines()
+                    .into_iter()
+                    .map(|(sign, line)| format!("{}{} {}{}\n", color, sign, line, RESET))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for FeatureDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (sign, line) in self.entries.iter().flat_map(DiffEntry::lines) {
+            writeln!(f, "{} {}", sign, line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Manifest {
+    /// Changes of `[features]` table since manifest is loaded
+    pub fn feature_diff(&self) -> Result<FeatureDiff, Error> {
+        let current = Self::collect_features(&self.path, self.document.as_table(), None)?;
+        let names = current
+            .keys()
+            .chain(self.original_features.keys())
+            .collect::<BTreeSet<_>>();
+
+        let entries = names
+            .into_iter()
+            .filter_map(
+                |name| match (self.original_features.get(name), current.get(name)) {
+                    (None, Some(new)) => Some(DiffEntry::Added {
+                        name: name.clone(),
+                        dependencies: sorted(new),
+                    }),
+                    (Some(old), None) => Some(DiffEntry::Removed {
+                        name: name.clone(),
+                        dependencies: sorted(old),
+                    }),
+                    (Some(old), Some(new)) if old != new => Some(DiffEntry::Updated {
+                        name: name.clone(),
+                        old: sorted(old),
+                        new: sorted(new),
+                    }),
+                    _ => None,
+                },
+            )
+            .collect();
+
+        Ok(FeatureDiff { entries })
+    }
+}
@@ -0,0 +1,51 @@
+use strum::IntoEnumIterator;
+
+use crate::{manifest::Named, DependencyHelper, Error, FeatureSet, Manifest};
+
+impl Manifest {
+    /// Add every variant of enum deriving `strum::EnumIter`, named with its `Display`(e.g. `strum::Display`).\
+    /// `ToFeatureName` is not required. Enabled features are returned.
+    pub fn add_strum_variants<
+        T: IntoEnumIterator + std::fmt::Display,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        self.add_named_variants(
+            T::iter().map(|feature| Named(feature.to_string(), feature)),
+            dependency_setter,
+        )
+    }
+
+    /// Same as `add_strum_variants`, named with `strum::IntoStaticStr` instead of `Display`
+    pub fn add_strum_static_variants<T: IntoEnumIterator, F: Fn(&'_ T, &mut DependencyHelper<'_>)>(
+        &mut self,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error>
+    where
+        for<'t> &'t T: Into<&'static str>,
+    {
+        self.add_named_variants(
+            T::iter().map(|feature| Named((&feature).into().to_string(), feature)),
+            dependency_setter,
+        )
+    }
+
+    fn add_named_variants<
+        T,
+        I: Iterator<Item = Named<T>>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        features: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        Ok(FeatureSet::from_named(
+            self.generate_features(features, |feature: &Named<T>, helper| {
+                dependency_setter(&feature.1, helper)
+            })?
+            .specified,
+        ))
+    }
+}
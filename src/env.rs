@@ -0,0 +1,49 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::Error;
+
+/// Read environment variable as path, without requiring it to be valid unicode
+pub(crate) fn var_path(key: &'static str) -> Result<PathBuf, Error> {
+    std::env::var_os(key)
+        .map(PathBuf::from)
+        .ok_or(Error::MissingEnv(key))
+}
+
+/// Read environment variable. Unset variable is `None`, and non-unicode value fails with `Error::NonUnicodeEnv`.
+pub(crate) fn var(key: &str) -> Result<Option<String>, Error> {
+    match std::env::var_os(key) {
+        Some(value) => value
+            .into_string()
+            .map(Some)
+            .map_err(|value| Error::NonUnicodeEnv(key.to_string(), value)),
+        None => Ok(None),
+    }
+}
+
+/// Check whether environment variable is set, regardless of its value
+pub(crate) fn is_set<K: Into<OsString>>(key: K) -> bool {
+    std::env::var_os(key.into()).is_some()
+}
+
+/// Environment variable overriding manifest loaded by `new_with_env` constructors
+pub const MANIFEST_OVERRIDE_ENV: &str = "MANIFEST_FEATURE_GEN_MANIFEST";
+
+/// Path of manifest to handle. `MANIFEST_FEATURE_GEN_MANIFEST` takes precedence over `CARGO_MANIFEST_DIR`.\
+/// Both manifest path and directory containing it are accepted for override.
+pub(crate) fn manifest_path() -> Result<PathBuf, Error> {
+    if let Some(path) = std::env::var_os(MANIFEST_OVERRIDE_ENV).filter(|path| !path.is_empty()) {
+        return Ok(manifest_path_of(PathBuf::from(path)));
+    }
+    let mut path = var_path("CARGO_MANIFEST_DIR")?;
+    path.push("Cargo.toml");
+    Ok(path)
+}
+
+/// Append `Cargo.toml` when `path` is directory
+pub(crate) fn manifest_path_of(path: PathBuf) -> PathBuf {
+    if path.is_dir() {
+        path.join("Cargo.toml")
+    } else {
+        path
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{Error, Manifest};
+
+/// Value exported for enabled feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExportValue {
+    Str(String),
+    Int(i64),
+}
+
+impl std::fmt::Display for ExportValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportValue::Str(value) => f.write_str(value),
+            ExportValue::Int(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<&str> for ExportValue {
+    fn from(value: &str) -> Self {
+        ExportValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for ExportValue {
+    fn from(value: String) -> Self {
+        ExportValue::Str(value)
+    }
+}
+
+impl From<i64> for ExportValue {
+    fn from(value: i64) -> Self {
+        ExportValue::Int(value)
+    }
+}
+
+/// Way of exporting enabled features from build script
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExportTarget {
+    /// `cargo:rustc-env=NAME=value`. Values of multiple enabled features are joined with `,`.
+    Env(String),
+    /// `cargo:rustc-cfg=value` for each enabled feature. `-` of value is replaced with `_`.
+    Cfg,
+    /// `cargo:rustc-cfg=key="value"` for each enabled feature
+    CfgKeyValue(String),
+    /// File in `OUT_DIR` listing value of each enabled feature per line
+    OutDirFile(String),
+    /// `cargo:key=value` metadata, passed to dependents as `DEP_<links>_<key>`.\
+    /// Values of multiple enabled features are joined with `,`.
+    LinksMetadata(String),
+}
+
+/// Description of features exported by build script, registered with `Manifest::add_export`.\
+/// Feature without value is exported with its name.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuildScriptExportDescriptor {
+    target: ExportTarget,
+    features: Vec<(String, Option<ExportValue>)>,
+    groups: Vec<String>,
+}
+
+fn value_of(feature: &(String, Option<ExportValue>)) -> String {
+    match &feature.1 {
+        Some(value) => value.to_string(),
+        None => feature.0.clone(),
+    }
+}
+
+fn cfg_of(value: &str) -> String {
+    value.replace('-', "_")
+}
+
+impl BuildScriptExportDescriptor {
+    /// Describe export to `target`
+    pub fn new(target: ExportTarget) -> Self {
+        Self {
+            target,
+            features: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Export feature with its name
+    pub fn feature(mut self, name: &str) -> Self {
+        self.features.push((name.to_string(), None));
+        self
+    }
+
+    /// Export feature with associated value(e.g. shared library basename of backend)
+    pub fn feature_with_value<V: Into<ExportValue>>(mut self, name: &str, value: V) -> Self {
+        self.features.push((name.to_string(), Some(value.into())));
+        self
+    }
+
+    /// Export every member of named exclusion group or parent feature, resolved while writing.\
+    /// Members not listed by `feature_with_value` are exported with their names.
+    pub fn group(mut self, group: &str) -> Self {
+        self.groups.push(group.to_string());
+        self
+    }
+
+    /// Listed features and members of groups, with associated values
+    fn resolve<M: Fn(&str) -> Vec<String>>(
+        &self,
+        members_of: M,
+    ) -> Vec<(String, Option<ExportValue>)> {
+        let mut features = self.features.clone();
+        for member in self.groups.iter().flat_map(|group| members_of(group)) {
+            if !features.iter().any(|(name, _)| name == &member) {
+                features.push((member, None));
+            }
+        }
+        features
+    }
+
+    /// `check-cfg` specs(e.g. `cfg(backend, values("gl", "vk"))`) declaring cfgs of export, with their cfg names
+    pub(crate) fn check_cfgs<M: Fn(&str) -> Vec<String>>(
+        &self,
+        members_of: M,
+    ) -> Vec<(String, String)> {
+        let features = self.resolve(members_of);
+        match &self.target {
+            ExportTarget::Cfg => features
+                .iter()
+                .map(|feature| {
+                    let name = cfg_of(&value_of(feature));
+                    (name.clone(), format!("cfg({})", name))
+                })
+                .collect(),
+            ExportTarget::CfgKeyValue(key) => {
+                let values = features
+                    .iter()
+                    .map(|feature| format!("{:?}", value_of(feature)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                vec![(key.clone(), format!("cfg({}, values({}))", key, values))]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Emit export. Emitted directives or written file paths are returned.
+    fn emit<M: Fn(&str) -> Vec<String> + Copy, P: Fn(&str) -> bool>(
+        &self,
+        members_of: M,
+        is_enabled: P,
+    ) -> Result<Vec<String>, Error> {
+        let features = self.resolve(members_of);
+        let enabled = features
+            .iter()
+            .filter(|(name, _)| is_enabled(name))
+            .map(value_of)
+            .collect::<Vec<_>>();
+
+        let directives = match &self.target {
+            ExportTarget::Env(name) if !enabled.is_empty() => {
+                vec![format!("cargo:rustc-env={}={}", name, enabled.join(","))]
+            }
+            ExportTarget::LinksMetadata(key) if !enabled.is_empty() => {
+                vec![format!("cargo:{}={}", key, enabled.join(","))]
+            }
+            ExportTarget::Env(_) | ExportTarget::LinksMetadata(_) => Vec::new(),
+            ExportTarget::Cfg => self
+                .check_cfgs(members_of)
+                .into_iter()
+                .map(|(_, spec)| format!("cargo:rustc-check-cfg={}", spec))
+                .chain(
+                    enabled
+                        .iter()
+                        .map(|value| format!("cargo:rustc-cfg={}", cfg_of(value))),
+                )
+                .collect(),
+            ExportTarget::CfgKeyValue(key) => self
+                .check_cfgs(members_of)
+                .into_iter()
+                .map(|(_, spec)| format!("cargo:rustc-check-cfg={}", spec))
+                .chain(
+                    enabled
+                        .iter()
+                        .map(|value| format!("cargo:rustc-cfg={}={:?}", key, value)),
+                )
+                .collect(),
+            ExportTarget::OutDirFile(file_name) => {
+                let mut path = crate::env::var_path("OUT_DIR")?;
+                path.push(file_name);
+                let content = enabled
+                    .iter()
+                    .map(|value| format!("{}\n", value))
+                    .collect::<String>();
+                std::fs::write(&path, content)?;
+                return Ok(vec![path.display().to_string()]);
+            }
+        };
+
+        for directive in &directives {
+            println!("{}", directive);
+        }
+        Ok(directives)
+    }
+}
+
+impl Manifest {
+    /// Register export of enabled features. Exports are emitted while writing, even when generation is skipped.
+    pub fn add_export(&mut self, descriptor: BuildScriptExportDescriptor) {
+        self.exports.push(descriptor);
+    }
+
+    /// Emit registered exports. Emitted directives and written file paths are returned.
+    pub(crate) fn emit_exports(&self) -> Result<Vec<String>, Error> {
+        let mut emitted = Vec::new();
+        for export in &self.exports {
+            emitted.extend(export.emit(
+                |group| self.export_group_members(group),
+                |feature| self.feature_source.is_enabled(feature),
+            )?);
+        }
+        Ok(emitted)
+    }
+
+    /// Members of named exclusion group or parent feature
+    pub(crate) fn export_group_members(&self, group: &str) -> Vec<String> {
+        self.exclusion_groups
+            .get(group)
+            .map(|group| group.members.clone())
+            .or_else(|| self.parent_features.get(group).cloned())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use toml_edit::{InlineTable, Item, Table, Value};
+
+use crate::{Dependency, Error, FeatureName, Manifest};
+
+const FACADE_HEADER: &str = concat!("# generated by ", env!("CARGO_CRATE_NAME"), "\n");
+
+/// Path of `path` relative to `base`. Both paths are expected to be absolute or relative to same directory.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let base = std::fs::canonicalize(base).unwrap_or_else(|_| base.to_path_buf());
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push(Component::ParentDir);
+    }
+    relative.extend(path.components().skip(common));
+    relative
+}
+
+impl Manifest {
+    /// Maintain facade crate `name` at `dir`, forwarding generated features to this crate.\
+    /// Exclusion groups are declared in facade with same members, and `src/lib.rs` re-exports this crate by its lib name.
+    /// Hand-written part of existing facade manifest is kept.
+    ///
+    /// Returns whether facade manifest is written. Call after all add calls.
+    pub fn write_facade(&self, dir: &Path, name: &str) -> Result<bool, Error> {
+        let package_name = self.package_name()?.to_string();
+        let manifest_dir = self.path.parent().unwrap_or(".".as_ref());
+        let facade_path = dir.join("Cargo.toml");
+        let source_dir = dir.join("src");
+        std::fs::create_dir_all(&source_dir)?;
+
+        if !facade_path.exists() {
+            let version = self
+                .document
+                .get("package")
+                .and_then(|package| package.get("version"))
+                .and_then(Item::as_str)
+                .unwrap_or("0.1.0");
+            std::fs::write(
+                &facade_path,
+                format!(
+                    "{}[package]\nname = {:?}\nversion = {:?}\nedition = \"2021\"\n\n[dependencies]\n",
+                    FACADE_HEADER, name, version
+                ),
+            )?;
+        }
+
+        let mut facade = Manifest::new(facade_path, false)?;
+        let dependencies = facade.document["dependencies"].or_insert(Item::Table(Table::new()));
+        let path = relative_path(manifest_dir, dir)
+            .to_string_lossy()
+            .replace('\\', "/");
+        match dependencies[package_name.as_str()].as_table_like_mut() {
+            Some(entry) => {
+                entry.insert("path", Item::Value(path.into()));
+            }
+            None => {
+                let mut entry = InlineTable::new();
+                entry.insert("path", path.into());
+                dependencies[package_name.as_str()] = Item::Value(Value::InlineTable(entry));
+            }
+        }
+
+        let forward = |feature: &FeatureName, deps: &mut crate::DependencyHelper<'_>| {
+            deps.add(Dependency::crate_feature(&package_name, feature.as_str()))
+        };
+        let mut grouped = Vec::new();
+        for (group, exclusion_group) in &self.exclusion_groups {
+            facade.declare_exclusion_group(group);
+            facade.try_add_features_to_exclusion_group(
+                group,
+                exclusion_group
+                    .members
+                    .iter()
+                    .map(|member| FeatureName::new_unchecked(member.clone())),
+                forward,
+            )?;
+            grouped.extend(exclusion_group.members.iter());
+        }
+        facade.try_add_features(
+            self.generated_features
+                .iter()
+                .filter(|feature| !grouped.contains(feature))
+                .map(|feature| FeatureName::new_unchecked(feature.clone())),
+            forward,
+        )?;
+
+        let mut code = crate::codegen::HEADER.to_string();
+        writeln!(code, "pub use {}::*;", self.lib_name()?).unwrap();
+        let lib_path = source_dir.join("lib.rs");
+        if std::fs::read_to_string(&lib_path).ok().as_deref() != Some(code.as_str()) {
+            std::fs::write(&lib_path, code)?;
+        }
+
+        facade.write()
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt::{Debug, Display};
+
+use crate::{Error, FeatureName};
+
+/// Split identifier into lowercase words, at `_`, `-`, whitespace and case boundaries(`HttpServer`, `HTTPServer`)
+fn words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[index - 1];
+            let next_is_lower = chars
+                .get(index + 1)
+                .map(|next| next.is_lowercase())
+                .unwrap_or_default();
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Convert identifier into kebab-case(`HttpServer` → `http-server`)
+pub fn kebab_case(name: &str) -> String {
+    words(name).join("-")
+}
+
+/// Convert identifier into snake_case(`HttpServer` → `http_server`)
+pub fn snake_case(name: &str) -> String {
+    words(name).join("_")
+}
+
+/// Convert identifier into UpperCamelCase(`http-server` → `HttpServer`)
+pub(crate) fn upper_camel_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Convert identifier into lowercase without separators(`HttpServer` → `httpserver`)
+pub fn lowercase(name: &str) -> String {
+    words(name).concat()
+}
+
+/// Case conversion of `Formatter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+    /// `http-server`
+    #[default]
+    Kebab,
+    /// `http_server`
+    Snake,
+    /// `httpserver`
+    Lower,
+    /// Kept as is
+    Preserve,
+}
+
+/// Builder of feature name formatter, used with `Manifest::add_features_with_formatter`
+///
+/// ```
+/// use manifest_feature_gen::Formatter;
+///
+/// let formatter = Formatter::kebab_case().prefix("backend-");
+/// assert_eq!(formatter.format("OpenGl").unwrap(), "backend-open-gl");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Formatter {
+    case: Case,
+    prefix: String,
+    suffix: String,
+}
+
+impl Formatter {
+    /// Formatter with specified case conversion
+    pub fn new(case: Case) -> Self {
+        Self {
+            case,
+            ..Default::default()
+        }
+    }
+
+    /// Formatter converting into kebab-case
+    pub fn kebab_case() -> Self {
+        Self::new(Case::Kebab)
+    }
+
+    /// Formatter converting into snake_case
+    pub fn snake_case() -> Self {
+        Self::new(Case::Snake)
+    }
+
+    /// Formatter converting into lowercase without separators
+    pub fn lowercase() -> Self {
+        Self::new(Case::Lower)
+    }
+
+    /// Prepend `prefix` to every name
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Append `suffix` to every name
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    /// Format name. Fails with `Error::InvalidFeatureName` when formatted name is not valid feature name.
+    pub fn format(&self, name: &str) -> Result<FeatureName, Error> {
+        let name = match self.case {
+            Case::Kebab => kebab_case(name),
+            Case::Snake => snake_case(name),
+            Case::Lower => lowercase(name),
+            Case::Preserve => name.to_string(),
+        };
+        FeatureName::validated(format!("{}{}{}", self.prefix, name, self.suffix))
+    }
+
+    /// Format `Debug` representation of value. Suitable for fieldless enum.
+    pub fn format_debug<T: Debug>(&self, value: &T) -> Result<FeatureName, Error> {
+        self.format(&format!("{:?}", value))
+    }
+
+    /// Format `Display` representation of value
+    pub fn format_display<T: Display>(&self, value: &T) -> Result<FeatureName, Error> {
+        self.format(&value.to_string())
+    }
+}
@@ -0,0 +1,187 @@
+use toml_edit::Value;
+
+use crate::{
+    manifest::{
+        generation_mark, insert_annotated_feature, insert_generated_feature, Named,
+        FEATURES_TABLE_NAME,
+    },
+    DependencyHelper, Error, FeatureName, Manifest, ToFeatureName,
+};
+
+/// Opaque reference to generated feature, returned by `Manifest::add_features_with_handles`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FeatureHandle {
+    name: FeatureName,
+    enabled: bool,
+}
+
+impl FeatureHandle {
+    /// Name of feature
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Validated name of feature
+    pub fn feature_name(&self) -> &FeatureName {
+        &self.name
+    }
+
+    /// Whether feature is enabled for current build
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl AsRef<str> for FeatureHandle {
+    fn as_ref(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+const DEFAULT_FEATURE_NAME: &str = "default";
+
+impl Manifest {
+    /// Same as `add_features`, but every feature is returned with its handle
+    pub fn add_features_with_handles<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<Vec<(T, FeatureHandle)>, Error> {
+        let features = feature_names.collect::<Vec<_>>();
+        let generated = self.generate_features(
+            features
+                .iter()
+                .enumerate()
+                .map(|(index, feature)| Named(feature.to_feature_name(), index)),
+            |named, helper| dependency_setter(&features[named.1], helper),
+        )?;
+        let enabled = generated
+            .specified
+            .into_iter()
+            .map(|named| named.1)
+            .collect::<Vec<_>>();
+
+        Ok(features
+            .into_iter()
+            .zip(generated.names)
+            .enumerate()
+            .map(|(index, (feature, name))| {
+                let enabled = enabled.contains(&index);
+                let name = FeatureName::new_unchecked(name);
+                (feature, FeatureHandle { name, enabled })
+            })
+            .collect())
+    }
+
+    /// Make `from` enable `to`
+    pub fn add_implication(
+        &mut self,
+        from: &FeatureHandle,
+        to: &FeatureHandle,
+    ) -> Result<(), Error> {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        let item = features
+            .get(from.name())
+            .ok_or_else(|| Error::UnknownFeature(from.name().to_string()))?;
+        let group = generation_mark(item).flatten();
+        let mut deps = item
+            .as_array()
+            .ok_or_else(|| Error::UnknownFeature(from.name().to_string()))?
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if !deps.iter().any(|dep| dep == to.name()) {
+            deps.push(to.name().to_string());
+            insert_annotated_feature(features, from.name(), deps, group.as_deref());
+            self.generated_dependencies
+                .entry(from.name().to_string())
+                .or_default()
+                .push(to.name().to_string());
+        }
+        Ok(())
+    }
+
+    /// Declare `a` and `b` as mutually exclusive.\
+    /// Fails immediately when both are enabled.
+    pub fn add_conflict(&mut self, a: &FeatureHandle, b: &FeatureHandle) -> Result<(), Error> {
+        self.anonymous_exclusion_groups
+            .push(vec![a.name().to_string(), b.name().to_string()]);
+        if a.enabled && b.enabled {
+            Err(Error::MutualExclusiveFeatureError(vec![
+                a.name().to_string(),
+                b.name().to_string(),
+            ]))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Add feature to `default` feature. `default` is created when it does not exist.\
+    /// `default` is marked as generated, keeping existing entries. Call on every run, as other generated features.
+    /// Entries added by previous run are dropped when not added again, only when state is recorded(`set_record_state`).
+    pub fn add_to_default(&mut self, handle: &FeatureHandle) {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        let mut default = match features.get(DEFAULT_FEATURE_NAME) {
+            Some(default) => default
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+            // entries generated by previous run are stale, others are manual ones
+            None => {
+                let stale = self
+                    .previous_state
+                    .as_ref()
+                    .and_then(|state| state.dependencies.get(DEFAULT_FEATURE_NAME));
+                self.previous_generated
+                    .get(DEFAULT_FEATURE_NAME)
+                    .into_iter()
+                    .flatten()
+                    .filter(|dep| !stale.is_some_and(|stale| stale.contains(dep)))
+                    .cloned()
+                    .collect()
+            }
+        };
+        if !default.iter().any(|dep| dep == handle.name()) {
+            default.push(handle.name().to_string());
+        }
+        insert_generated_feature(features, DEFAULT_FEATURE_NAME, default);
+
+        let generated = self
+            .generated_dependencies
+            .entry(DEFAULT_FEATURE_NAME.to_string())
+            .or_default();
+        if !generated.iter().any(|dep| dep == handle.name()) {
+            generated.push(handle.name().to_string());
+        }
+        if !self
+            .generated_features
+            .iter()
+            .any(|feature| feature == DEFAULT_FEATURE_NAME)
+        {
+            self.generated_features
+                .push(DEFAULT_FEATURE_NAME.to_string());
+        }
+    }
+
+    /// Attach documentation comment(`## ...`) above generated feature
+    pub fn set_feature_doc(&mut self, handle: &FeatureHandle, doc: &str) -> Result<(), Error> {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        let mut key = features
+            .key_mut(handle.name())
+            .ok_or_else(|| Error::UnknownFeature(handle.name().to_string()))?;
+        let prefix = doc
+            .lines()
+            .map(|line| format!("## {}\n", line))
+            .collect::<String>();
+        key.leaf_decor_mut().set_prefix(prefix);
+        Ok(())
+    }
+}
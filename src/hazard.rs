@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use crate::Manifest;
+
+/// Mutually exclusive group, which can break under cargo's additive feature unification
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnificationHazard {
+    /// Name of exclusion group. `None` for group added by `add_mutually_exclusive_features`.
+    pub group: Option<String>,
+    pub members: Vec<String>,
+    /// Usage patterns enabling multiple members at the same time
+    pub conflicts: Vec<String>,
+    /// Alternative pattern not relying on features
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnificationHazard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.group {
+            Some(group) => write!(f, "group '{}'", group)?,
+            None => write!(f, "mutually exclusive features")?,
+        }
+        writeln!(
+            f,
+            "({}) breaks under feature unification",
+            self.members.join(", ")
+        )?;
+        for conflict in &self.conflicts {
+            writeln!(f, "  - {}", conflict)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            writeln!(f, "  help: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+const SUGGESTION: &str = "select member with key-value cfg(`ExportTarget::CfgKeyValue`) or environment variable(`ListEnvFeatureSource`), which are not unified across dependents";
+
+impl Manifest {
+    /// Analyze mutually exclusive groups, which break when dependents enable different members.\
+    /// Conflicting usage patterns found in `[features]` are reported, with alternative pattern if `suggest` is set.
+    pub fn unification_hazards(&self, suggest: bool) -> Vec<UnificationHazard> {
+        let groups = self
+            .exclusion_groups
+            .iter()
+            .map(|(group, exclusion_group)| (Some(group.clone()), &exclusion_group.members))
+            .chain(
+                self.anonymous_exclusion_groups
+                    .iter()
+                    .map(|members| (None, members)),
+            )
+            .filter(|(_, members)| members.len() > 1);
+
+        groups
+            .map(|(group, members)| {
+                let mut conflicts = vec![format!(
+                    "dependents enabling different members(e.g. `{}` and `{}`) are unified into enabling both",
+                    members[0], members[1]
+                )];
+
+                // other features enabling members
+                let enablers = self
+                    .features()
+                    .map(|(feature, _)| feature)
+                    .filter(|feature| !members.iter().any(|member| member == feature))
+                    .filter_map(|feature| {
+                        let enabled = self
+                            .resolve(&[feature])
+                            .features
+                            .into_iter()
+                            .filter(|enabled| members.contains(enabled))
+                            .collect::<Vec<_>>();
+                        (!enabled.is_empty()).then(|| (feature.to_string(), enabled))
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                for (feature, enabled) in &enablers {
+                    if enabled.len() > 1 {
+                        conflicts.push(format!(
+                            "feature `{}` always enables multiple members - {}",
+                            feature,
+                            enabled.join(", ")
+                        ));
+                    } else if feature == "default" {
+                        conflicts.push(format!(
+                            "`default` enables `{}`, so dependents selecting other member conflict unless `default-features = false`",
+                            enabled[0]
+                        ));
+                    }
+                }
+                let single = enablers
+                    .iter()
+                    .filter(|(feature, enabled)| enabled.len() == 1 && *feature != "default")
+                    .collect::<Vec<_>>();
+                for (index, (a, a_enabled)) in single.iter().enumerate() {
+                    for (b, b_enabled) in &single[(index + 1)..] {
+                        if a_enabled != b_enabled {
+                            conflicts.push(format!(
+                                "features `{}` and `{}` enable different members(`{}`, `{}`)",
+                                a, b, a_enabled[0], b_enabled[0]
+                            ));
+                        }
+                    }
+                }
+
+                UnificationHazard {
+                    group,
+                    members: members.clone(),
+                    conflicts,
+                    suggestion: suggest.then(|| SUGGESTION.to_string()),
+                }
+            })
+            .collect()
+    }
+}
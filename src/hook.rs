@@ -0,0 +1,45 @@
+use crate::{GenerationReport, Manifest};
+
+type Hook = Box<dyn Fn(&str)>;
+
+/// Callbacks registered for changes of features
+#[derive(Default)]
+pub(crate) struct Hooks {
+    added: Vec<Hook>,
+    removed: Vec<Hook>,
+    modified: Vec<Hook>,
+}
+
+impl Hooks {
+    pub(crate) fn run(&self, report: &GenerationReport) {
+        for changes in report.changes.values() {
+            for (hooks, features) in [
+                (&self.added, &changes.added),
+                (&self.removed, &changes.removed),
+                (&self.modified, &changes.updated),
+            ] {
+                for feature in features {
+                    hooks.iter().for_each(|hook| hook(feature.as_str()));
+                }
+            }
+        }
+    }
+}
+
+impl Manifest {
+    /// Register callback invoked with name of each added feature, after manifest is written.\
+    /// Callbacks are not invoked when manifest is unchanged or not written(e.g. read-only manifest).
+    pub fn on_feature_added<F: Fn(&str) + 'static>(&mut self, hook: F) {
+        self.hooks.added.push(Box::new(hook));
+    }
+
+    /// Register callback invoked with name of each removed feature, after manifest is written
+    pub fn on_feature_removed<F: Fn(&str) + 'static>(&mut self, hook: F) {
+        self.hooks.removed.push(Box::new(hook));
+    }
+
+    /// Register callback invoked with name of each feature whose dependencies are changed, after manifest is written
+    pub fn on_feature_modified<F: Fn(&str) + 'static>(&mut self, hook: F) {
+        self.hooks.modified.push(Box::new(hook));
+    }
+}
@@ -0,0 +1,56 @@
+use toml_edit::{Item, Value};
+
+/// Kind of manifest item, used to describe malformed content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    None,
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Datetime,
+    Array,
+    InlineTable,
+    Table,
+    ArrayOfTables,
+}
+
+impl ItemKind {
+    pub fn of_item(item: &Item) -> Self {
+        match item {
+            Item::None => ItemKind::None,
+            Item::Value(value) => Self::of_value(value),
+            Item::Table(_) => ItemKind::Table,
+            Item::ArrayOfTables(_) => ItemKind::ArrayOfTables,
+        }
+    }
+
+    pub fn of_value(value: &Value) -> Self {
+        match value {
+            Value::String(_) => ItemKind::String,
+            Value::Integer(_) => ItemKind::Integer,
+            Value::Float(_) => ItemKind::Float,
+            Value::Boolean(_) => ItemKind::Boolean,
+            Value::Datetime(_) => ItemKind::Datetime,
+            Value::Array(_) => ItemKind::Array,
+            Value::InlineTable(_) => ItemKind::InlineTable,
+        }
+    }
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ItemKind::None => "nothing",
+            ItemKind::String => "string",
+            ItemKind::Integer => "integer",
+            ItemKind::Float => "float",
+            ItemKind::Boolean => "boolean",
+            ItemKind::Datetime => "datetime",
+            ItemKind::Array => "array",
+            ItemKind::InlineTable => "inline table",
+            ItemKind::Table => "table",
+            ItemKind::ArrayOfTables => "array of tables",
+        })
+    }
+}
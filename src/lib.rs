@@ -30,26 +30,367 @@
 /// Possible errors while using manifest-feature-gen
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("Cannot find environment variable CARGO_MANIFEST_DIR")]
-    EnvError,
+    #[error("Cannot find environment variable {0}")]
+    MissingEnv(&'static str),
+    #[error("Environment variable {0} is not valid unicode - {1:?}")]
+    NonUnicodeEnv(String, std::ffi::OsString),
     #[error("IO error - {0:?}")]
     IoError(#[from] std::io::Error),
     #[error("Failed to parse manifest - {0:?}")]
     ParseError(#[from] toml_edit::TomlError),
-    #[error("Manifest is malformed - {0}")]
-    MalformedManifest(String),
+    #[error("IO error of manifest({}) - {source:?}", path.display())]
+    ManifestIoError {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse manifest({}) - {source}", path.display())]
+    ManifestParseError {
+        path: std::path::PathBuf,
+        source: Box<toml_edit::TomlError>,
+        /// Text failed to parse, when kept. Otherwise, it is read again from `path` for diagnostic.
+        content: Option<String>,
+    },
+    #[error(
+        "Manifest({}) is malformed - {} is expected to be {expected}, but found {found}{}",
+        path.display(),
+        key.join("."),
+        location.as_ref().map(|location| format!(" at {}", location)).unwrap_or_default()
+    )]
+    MalformedManifest {
+        path: std::path::PathBuf,
+        key: Vec<String>,
+        expected: ItemKind,
+        found: ItemKind,
+        location: Option<SourceLocation>,
+    },
     #[error("Mutually exclusive features are enabled at the same time - {0:?}")]
     MutualExclusiveFeatureError(Vec<String>),
+    #[error("None of required features is enabled - {0:?}")]
+    RequiredFeatureError(Vec<String>),
+    #[error("Invalid dependency of feature({feature}) - {source}")]
+    InvalidDependency {
+        feature: String,
+        source: DependencyError,
+    },
+    #[error("Feature({feature}) has dependencies not generated - {dependencies:?}")]
+    ManualEditConflict {
+        feature: String,
+        dependencies: Vec<String>,
+    },
+    #[error(
+        "Invalid feature name({name}){} - {source}",
+        group.as_ref().map(|group| format!(" in group({})", group)).unwrap_or_default()
+    )]
+    InvalidFeatureName {
+        name: String,
+        group: Option<String>,
+        source: FeatureNameError,
+    },
+    #[error("Feature is not generated - {0}")]
+    UnknownFeature(String),
+    #[error("Feature already exists - {0}")]
+    FeatureAlreadyExists(String),
+    #[error("Dependency is not declared in manifest - {0}")]
+    UnknownDependency(String),
+    #[error("Cannot resolve dependency - {0}")]
+    UnresolvedDependency(String),
+    #[error("Cannot recognize version of rustc - {0}")]
+    RustcVersionError(String),
+    #[error("Group is not declared - {0}")]
+    UnknownGroup(String),
+    #[error("Features({first}, {second}) are mapped to the same variant - {variant}")]
+    VariantNameCollision {
+        variant: String,
+        first: String,
+        second: String,
+    },
+    #[error("Parent feature({parent}) is already a descendant of {child}")]
+    ParentFeatureCycle { parent: String, child: String },
+    #[error(
+        "Selected feature is not generated - {name}{}",
+        suggestion.as_ref().map(|suggestion| format!(". Did you mean {}?", suggestion)).unwrap_or_default()
+    )]
+    UnknownSelectedFeature {
+        name: String,
+        suggestion: Option<String>,
+    },
+    #[error("Feature is pinned - {0}")]
+    PinnedFeature(String),
+    #[error("Generated features differ from lockfile({}). Update lockfile after review", .0.display())]
+    LockMismatch(std::path::PathBuf),
+    #[error(
+        "Feature({feature}) of manifest({}) refers to feature not declared - {dependency}",
+        path.display()
+    )]
+    InconsistentFeature {
+        path: std::path::PathBuf,
+        feature: String,
+        dependency: String,
+    },
+    #[error("Features require newer rust than rust-version({rust_version}) - {features:?}")]
+    IncompatibleRustVersion {
+        rust_version: String,
+        features: Vec<String>,
+    },
+    #[error("Too many keywords, crates.io accepts up to 5 - {0:?}")]
+    TooManyKeywords(Vec<String>),
+    #[error("Profile setting is not supported - {0}")]
+    UnsupportedProfileKey(String),
+    #[error("Manifest({}) does not belong to any workspace", .0.display())]
+    NotInWorkspace(std::path::PathBuf),
+    #[error("Recorded generation state has unsupported version - {0}")]
+    UnsupportedStateVersion(i64),
+    #[error(
+        "Features used in source are not declared - {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    UnknownFeatureUsage(Vec<FeatureUsage>),
     // This is actually not an error. But, handling this as error can prevent useless build.
     #[error("Manifest is changed. Please re-run the build")]
     ManifestChanged,
 }
 
+impl Error {
+    pub(crate) fn malformed(
+        path: &std::path::Path,
+        key: &[&str],
+        expected: ItemKind,
+        found: ItemKind,
+        location: Option<SourceLocation>,
+    ) -> Self {
+        Error::MalformedManifest {
+            path: path.to_path_buf(),
+            key: key.iter().map(|key| key.to_string()).collect(),
+            expected,
+            found,
+            location,
+        }
+    }
+
+    /// Attach group to `Error::InvalidFeatureName`
+    pub(crate) fn in_group(self, group: &str) -> Self {
+        match self {
+            Error::InvalidFeatureName { name, source, .. } => Error::InvalidFeatureName {
+                name,
+                group: Some(group.to_string()),
+                source,
+            },
+            e => e,
+        }
+    }
+
+    /// Attach `path` of manifest to `Error::IoError` and `Error::ParseError`
+    pub(crate) fn at_manifest(self, path: &std::path::Path) -> Self {
+        match self {
+            Error::IoError(source) => Error::ManifestIoError {
+                path: path.to_path_buf(),
+                source,
+            },
+            Error::ParseError(source) => Error::ManifestParseError {
+                path: path.to_path_buf(),
+                source: Box::new(source),
+                content: None,
+            },
+            e => e,
+        }
+    }
+
+    /// Same as `at_manifest`, keeping `content` failed to parse
+    pub(crate) fn parsing_manifest(self, path: &std::path::Path, content: &str) -> Self {
+        match self.at_manifest(path) {
+            Error::ManifestParseError { path, source, .. } => Error::ManifestParseError {
+                path,
+                source,
+                content: Some(content.to_string()),
+            },
+            e => e,
+        }
+    }
+
+    /// Path of manifest(or lockfile for `Error::LockMismatch`) related with this error, if any
+    pub fn manifest_path(&self) -> Option<&std::path::Path> {
+        match self {
+            Error::MalformedManifest { path, .. }
+            | Error::ManifestIoError { path, .. }
+            | Error::ManifestParseError { path, .. }
+            | Error::InconsistentFeature { path, .. }
+            | Error::NotInWorkspace(path)
+            | Error::LockMismatch(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
 /// Provide feature name for write to cargo manifest.
 /// Recommend write in snake_case or kebab-case
 pub trait ToFeatureName {
     fn to_feature_name(&self) -> String;
 }
 
+impl ToFeatureName for str {
+    fn to_feature_name(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToFeatureName for String {
+    fn to_feature_name(&self) -> String {
+        self.clone()
+    }
+}
+
+impl ToFeatureName for std::borrow::Cow<'_, str> {
+    fn to_feature_name(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<T: ToFeatureName + ?Sized> ToFeatureName for &T {
+    fn to_feature_name(&self) -> String {
+        (**self).to_feature_name()
+    }
+}
+
+/// Provide every variant of features, for `Manifest::add_all_variants`.
+/// Implement alongside `ToFeatureName`, so list of features never drifts from enum definition.
+pub trait EnumerateFeatures: Sized {
+    /// Every variant, in declaration order
+    fn all_variants() -> Vec<Self>;
+}
+
+/// Parse feature name back into value, for typed `Manifest::enabled_features_as`.
+///
+/// For `EnumerateFeatures` type, implement with `from_feature_name_by_variants`.
+/// ```
+/// use manifest_feature_gen::{from_feature_name_by_variants, EnumerateFeatures, FromFeatureName, ToFeatureName};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Backend {
+///     Tokio,
+///     AsyncStd,
+/// }
+///
+/// impl ToFeatureName for Backend {
+///     fn to_feature_name(&self) -> String {
+///         match self {
+///             Backend::Tokio => "tokio".to_string(),
+///             Backend::AsyncStd => "async-std".to_string(),
+///         }
+///     }
+/// }
+///
+/// impl EnumerateFeatures for Backend {
+///     fn all_variants() -> Vec<Self> {
+///         vec![Backend::Tokio, Backend::AsyncStd]
+///     }
+/// }
+///
+/// impl FromFeatureName for Backend {
+///     fn from_feature_name(name: &str) -> Option<Self> {
+///         from_feature_name_by_variants(name)
+///     }
+/// }
+///
+/// assert_eq!(Backend::from_feature_name("async-std"), Some(Backend::AsyncStd));
+/// ```
+pub trait FromFeatureName: Sized {
+    fn from_feature_name(name: &str) -> Option<Self>;
+}
+
+/// Find variant named `name` among `EnumerateFeatures::all_variants`
+pub fn from_feature_name_by_variants<T: EnumerateFeatures + ToFeatureName>(
+    name: &str,
+) -> Option<T> {
+    T::all_variants()
+        .into_iter()
+        .find(|variant| variant.to_feature_name() == name)
+}
+
+mod check_cfg;
+mod ci;
+mod codegen;
+pub use codegen::*;
+mod combination;
+pub use combination::*;
+mod dependencies;
+#[cfg(feature = "miette")]
+mod diagnostic;
+#[cfg(feature = "miette")]
+pub use diagnostic::*;
+mod diff;
+pub use diff::*;
+#[cfg(feature = "strum")]
+mod enum_iter;
+mod env;
+pub use env::MANIFEST_OVERRIDE_ENV;
+mod export;
+pub use export::*;
+mod facade;
+mod formatter;
+pub use formatter::*;
+mod handle;
+pub use handle::*;
+mod hazard;
+pub use hazard::UnificationHazard;
+mod hook;
+mod json;
+mod kind;
+pub use kind::*;
+mod lint;
+pub use lint::*;
+mod location;
+pub use location::*;
+mod lock;
 mod manifest;
 pub use manifest::*;
+mod manifest_set;
+pub use manifest_set::ManifestSet;
+#[cfg(feature = "testing")]
+mod matrix;
+#[cfg(feature = "pkg-config")]
+mod native;
+#[cfg(feature = "pkg-config")]
+pub use native::*;
+mod mirror;
+pub use mirror::*;
+mod msrv;
+mod name;
+pub use name::*;
+mod options;
+pub use options::*;
+mod package;
+pub use package::PackageList;
+mod patch;
+pub use patch::Patch;
+mod probe;
+pub use probe::*;
+mod profile;
+pub use profile::*;
+mod publish;
+mod query;
+mod reader;
+pub use reader::*;
+mod report;
+pub use report::*;
+mod resolve;
+pub use resolve::ResolvedSet;
+mod selection;
+pub use selection::*;
+mod set;
+pub use set::*;
+mod source;
+pub use source::*;
+mod stamp;
+pub use stamp::*;
+mod state;
+pub use state::GenerationState;
+mod target;
+pub use target::TargetDefault;
+mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transaction;
+mod usage;
+pub use usage::FeatureUsage;
+mod workspace;
+pub use workspace::{DependencySpec, WorkspaceDependency, WorkspaceManifest};
@@ -0,0 +1,120 @@
+use crate::Manifest;
+
+/// Thresholds of `Manifest::lints`. Each lint is disabled when its threshold is `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LintOptions {
+    /// Maximum number of features in a group(exclusion group or parent feature)
+    pub max_group_size: Option<usize>,
+    /// Maximum number of direct dependencies of a feature
+    pub max_fan_out: Option<usize>,
+    /// Maximum number of combinations iterated by `Manifest::feature_combinations`
+    pub max_combinations: Option<usize>,
+}
+
+/// Feature set growing unmanageable, found by `Manifest::lints`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeatureLint {
+    /// Group has more features than `max_group_size`. `group` is `None` for group added by `add_mutually_exclusive_features`.
+    LargeGroup {
+        group: Option<String>,
+        size: usize,
+        max: usize,
+    },
+    /// Feature has more direct dependencies than `max_fan_out`
+    LargeFanOut {
+        feature: String,
+        fan_out: usize,
+        max: usize,
+    },
+    /// Feature combinations exceed `max_combinations`. Combinations are counted only up to threshold.
+    LargeMatrix { max: usize },
+}
+
+impl std::fmt::Display for FeatureLint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeatureLint::LargeGroup {
+                group: Some(group),
+                size,
+                max,
+            } => write!(
+                f,
+                "group '{}' has {} features, more than {}",
+                group, size, max
+            ),
+            FeatureLint::LargeGroup {
+                group: None,
+                size,
+                max,
+            } => write!(
+                f,
+                "mutually exclusive group has {} features, more than {}",
+                size, max
+            ),
+            FeatureLint::LargeFanOut {
+                feature,
+                fan_out,
+                max,
+            } => write!(
+                f,
+                "feature({}) has {} dependencies, more than {}",
+                feature, fan_out, max
+            ),
+            FeatureLint::LargeMatrix { max } => {
+                write!(f, "feature matrix has more than {} combinations", max)
+            }
+        }
+    }
+}
+
+impl Manifest {
+    /// Check size of groups, fan-out of features and size of feature matrix against `options`.\
+    /// Nothing is checked with default options.
+    pub fn lints(&self, options: &LintOptions) -> Vec<FeatureLint> {
+        let mut lints = Vec::new();
+
+        if let Some(max) = options.max_group_size {
+            let groups = self
+                .exclusion_groups
+                .iter()
+                .map(|(group, exclusion_group)| {
+                    (Some(group.clone()), exclusion_group.members.len())
+                })
+                .chain(
+                    self.anonymous_exclusion_groups
+                        .iter()
+                        .map(|members| (None, members.len())),
+                )
+                .chain(
+                    self.parent_features
+                        .iter()
+                        .map(|(parent, children)| (Some(parent.clone()), children.len())),
+                );
+            lints.extend(
+                groups
+                    .filter(|(_, size)| *size > max)
+                    .map(|(group, size)| FeatureLint::LargeGroup { group, size, max }),
+            );
+        }
+
+        if let Some(max) = options.max_fan_out {
+            lints.extend(self.features().filter(|(_, deps)| deps.len() > max).map(
+                |(feature, deps)| FeatureLint::LargeFanOut {
+                    feature: feature.to_string(),
+                    fan_out: deps.len(),
+                    max,
+                },
+            ));
+        }
+
+        if let Some(max) = options.max_combinations {
+            if self.feature_combinations().nth(max).is_some() {
+                lints.push(FeatureLint::LargeMatrix { max });
+            }
+        }
+
+        lints
+    }
+}
@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+const SNIPPET_MAX_LENGTH: usize = 40;
+
+/// Location of content in manifest source
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLocation {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number, counted in characters
+    pub column: usize,
+    /// First line of the located content
+    pub snippet: String,
+}
+
+impl SourceLocation {
+    /// Convert byte span of `source` to line and column
+    pub fn from_span(source: &str, span: Range<usize>) -> Option<Self> {
+        let preceding = source.get(..span.start)?;
+        let line_start = preceding.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line = preceding.matches('\n').count() + 1;
+        let column = preceding[line_start..].chars().count() + 1;
+
+        let content = source.get(span)?;
+        let content = content.lines().next().unwrap_or_default().trim_end();
+        let mut snippet = content.chars().take(SNIPPET_MAX_LENGTH).collect::<String>();
+        if snippet.len() < content.len() {
+            snippet.push_str("...");
+        }
+
+        Some(Self {
+            line,
+            column,
+            snippet,
+        })
+    }
+
+    pub(crate) fn locate(source: Option<&str>, span: Option<Range<usize>>) -> Option<Self> {
+        Self::from_span(source?, span?)
+    }
+}
+
+impl std::fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: `{}`",
+            self.line, self.column, self.snippet
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_span() {
+        let source = "[features]\nä = [\"b\"]\n";
+        let location = SourceLocation::from_span(source, 11..source.len()).unwrap();
+        assert_eq!((location.line, location.column), (2, 1));
+        assert_eq!(location.snippet, "ä = [\"b\"]");
+
+        let offset = source.find("[\"b").unwrap();
+        let location = SourceLocation::from_span(source, offset..offset + 1).unwrap();
+        assert_eq!((location.line, location.column), (2, 5));
+        assert!(SourceLocation::from_span(source, 0..source.len() + 1).is_none());
+    }
+
+    #[test]
+    fn long_snippet_is_truncated() {
+        let source = "x".repeat(SNIPPET_MAX_LENGTH + 1);
+        let location = SourceLocation::from_span(&source, 0..source.len()).unwrap();
+        assert_eq!(location.snippet, format!("{}...", &source[1..]));
+    }
+}
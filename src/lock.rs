@@ -0,0 +1,123 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use toml_edit::{Array, DocumentMut, Item, Table, Value};
+
+use crate::{
+    manifest::{generation_mark, insert_generated_feature, FEATURES_TABLE_NAME},
+    Error, Manifest,
+};
+
+const LOCK_FILE_NAME: &str = "feature-gen.lock";
+const LOCK_HEADER: &str = concat!(
+    "# This file is generated by ",
+    env!("CARGO_PKG_NAME"),
+    ". Do not edit by hand.\n"
+);
+const CONSTRAINTS_TABLE_NAME: &str = "constraints";
+const EXCLUSIVE_KEY: &str = "exclusive";
+
+impl Manifest {
+    /// Set path of feature lockfile. `feature-gen.lock` next to manifest is used by default.
+    pub fn set_lock_path(&mut self, path: PathBuf) {
+        self.lock_path = Some(path);
+    }
+
+    /// Path of feature lockfile
+    pub fn lock_path(&self) -> PathBuf {
+        self.lock_path.clone().unwrap_or_else(|| {
+            self.path
+                .parent()
+                .unwrap_or(".".as_ref())
+                .join(LOCK_FILE_NAME)
+        })
+    }
+
+    /// Content of lockfile capturing generated features and constraints between them.\
+    /// Tiers and parent features are applied to copy of `[features]`, leaving document untouched.
+    fn lock_content(&self) -> String {
+        let mut features = self.document[FEATURES_TABLE_NAME]
+            .as_table()
+            .cloned()
+            .unwrap_or_default();
+        for (name, members) in self
+            .tier_entries()
+            .into_iter()
+            .chain(self.parent_features.clone())
+        {
+            insert_generated_feature(&mut features, &name, members);
+        }
+
+        let generated = features
+            .iter()
+            .filter(|(_, item)| generation_mark(item).is_some())
+            .map(|(feature, item)| {
+                let mut deps = item
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                deps.sort();
+                (feature.to_string(), deps)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut exclusive = self
+            .all_exclusion_groups()
+            .map(<[String]>::to_vec)
+            .collect::<Vec<_>>();
+        exclusive.sort();
+
+        let mut features = Table::new();
+        for (feature, deps) in generated {
+            features.insert(&feature, Item::Value(Value::Array(Array::from_iter(deps))));
+        }
+        let mut constraints = Table::new();
+        constraints.insert(
+            EXCLUSIVE_KEY,
+            Item::Value(Value::Array(Array::from_iter(
+                exclusive
+                    .into_iter()
+                    .map(|group| Value::Array(Array::from_iter(group))),
+            ))),
+        );
+
+        let mut document = DocumentMut::new();
+        document.insert(FEATURES_TABLE_NAME, Item::Table(features));
+        document.insert(CONSTRAINTS_TABLE_NAME, Item::Table(constraints));
+        format!("{}{}", LOCK_HEADER, document)
+    }
+
+    /// Check generated features against lockfile, for CI.\
+    /// Fails with `Error::LockMismatch` when lockfile is missing or differs. Call after all add calls.
+    pub fn check_against_lock(&self) -> Result<(), Error> {
+        let path = self.lock_path();
+        let locked = match std::fs::read_to_string(&path) {
+            Ok(locked) => locked,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::LockMismatch(path))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if locked.replace("\r\n", "\n") == self.lock_content() {
+            Ok(())
+        } else {
+            Err(Error::LockMismatch(path))
+        }
+    }
+
+    /// Write lockfile from generated features. Call after all add calls.\
+    /// Returns whether lockfile is changed.
+    pub fn update_lock(&self) -> Result<bool, Error> {
+        let path = self.lock_path();
+        let content = self.lock_content();
+        match std::fs::read_to_string(&path) {
+            Ok(locked) if locked == content => Ok(false),
+            _ => {
+                std::fs::write(&path, content)?;
+                Ok(true)
+            }
+        }
+    }
+}
@@ -1,13 +1,19 @@
 use std::{
-    collections::{HashMap, HashSet},
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     hash::Hash,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use fallible_iterator::FallibleIterator;
-use toml_edit::{Array, DocumentMut, Formatted, Item, Table, Value};
+use toml_edit::{Array, Formatted, ImDocument, Item, Table, Value};
 
-use crate::{Error, ToFeatureName};
+use crate::{
+    load_feature_definitions, state::GenerationState, BuildScriptExportDescriptor,
+    EnumerateFeatures, EnvFeatureSource, Error, FeatureCombinations, FeatureDefinition, FeatureSet,
+    FeatureSource, GenerationReport, IntoFeatureName, ItemKind, LineEnding, SourceLocation,
+    ToFeatureName, UnknownFeaturePolicy, WriteOptions,
+};
 
 /// Cargo manifest representation for editing features.
 ///
@@ -15,33 +21,338 @@ use crate::{Error, ToFeatureName};
 /// Generated features are identified by comment.\
 /// For correct working, Do not remove auto-generated marking comment.
 pub struct Manifest {
-    path: PathBuf,
-    original_features: HashMap<String, HashSet<String>>,
-    document: toml_edit::DocumentMut,
-    prevent_build_when_changed: bool,
+    pub(crate) path: PathBuf,
+    pub(crate) original_features: HashMap<String, HashSet<String>>,
+    pub(crate) document: toml_edit::DocumentMut,
+    pub(crate) prevent_build_when_changed: bool,
+    read_only: bool,
+    packaged: bool,
+    generation_policy: GenerationPolicy,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) exclusion_groups: BTreeMap<String, ExclusionGroup>,
+    pub(crate) anonymous_exclusion_groups: Vec<Vec<String>>,
+    pub(crate) generated_features: Vec<String>,
+    tiers: Vec<(String, TierMembership)>,
+    pub(crate) parent_features: BTreeMap<String, Vec<String>>,
+    aliases: Vec<(String, String)>,
+    deprecated_features: BTreeMap<String, Option<String>>,
+    adopt_policy: MergePolicy,
+    original_content: String,
+    pub(crate) previous_generated: HashMap<String, Vec<String>>,
+    regeneration_policy: MergePolicy,
+    group_regeneration_policies: HashMap<String, MergePolicy>,
+    pub(crate) generated_dependencies: BTreeMap<String, Vec<String>>,
+    pub(crate) optional_dependencies: BTreeSet<String>,
+    annotate_groups: bool,
+    pub(crate) previous_groups: HashMap<String, String>,
+    record_state: bool,
+    pub(crate) previous_state: Option<GenerationState>,
+    pub(crate) lock_path: Option<PathBuf>,
+    pub(crate) pinned_features: HashSet<String>,
+    pub(crate) hooks: crate::hook::Hooks,
+    pub(crate) feature_source: Box<dyn FeatureSource>,
+    pub(crate) exports: Vec<BuildScriptExportDescriptor>,
+    pub(crate) template_path: Option<PathBuf>,
+    pub(crate) maintain_check_cfg: bool,
+    pub(crate) patches: Vec<(String, String, crate::Patch)>,
+    pub(crate) rust_versions: BTreeMap<String, crate::RustcVersion>,
+    pub(crate) native_probe: crate::NativeProbeReport,
+}
+
+/// Policy merging existing dependencies of feature with generated ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// Existing dependencies are replaced with generated ones
+    #[default]
+    Replace,
+    /// Existing dependencies are kept along with generated ones
+    MergeUnion,
+    /// Fail with `Error::ManualEditConflict` when existing dependencies are not generated
+    FailOnManualEdit,
+}
+
+impl MergePolicy {
+    fn merge(
+        self,
+        feature: &str,
+        existing: Vec<String>,
+        mut generated: Vec<String>,
+    ) -> Result<Vec<String>, Error> {
+        let manual = existing
+            .into_iter()
+            .filter(|dep| !generated.contains(dep))
+            .collect::<Vec<_>>();
+        match self {
+            MergePolicy::Replace => {}
+            MergePolicy::MergeUnion => generated.extend(manual),
+            MergePolicy::FailOnManualEdit => {
+                if !manual.is_empty() {
+                    return Err(Error::ManualEditConflict {
+                        feature: feature.to_string(),
+                        dependencies: manual,
+                    });
+                }
+            }
+        }
+        Ok(generated)
+    }
+}
+
+/// Membership rule of tier meta-feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TierMembership {
+    /// Every generated feature not belonging to any mutually exclusive group
+    NonExclusive,
+    /// Every generated feature, including mutually exclusive ones
+    AllGenerated,
+    /// Specified features
+    Features(Vec<String>),
+}
+
+/// Mutually exclusive features collected across multiple add calls
+#[derive(Debug, Default)]
+pub(crate) struct ExclusionGroup {
+    pub(crate) members: Vec<String>,
+    pub(crate) enabled: Vec<String>,
+    /// Prefix prepended to names of members
+    pub(crate) prefix: String,
+    pub(crate) target_default: Option<crate::TargetDefault>,
+}
+
+/// Strategy choosing one feature when multiple mutually exclusive features are enabled
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ExclusiveStrategy {
+    /// Fail with `Error::MutualExclusiveFeatureError`
+    #[default]
+    Error,
+    /// Choose the first enabled feature in declaration order
+    FirstWins,
+    /// Choose the enabled feature placed first in specified feature names.\
+    /// Features not listed are considered after listed ones, in declaration order.
+    Priority(Vec<String>),
 }
 
+impl ExclusiveStrategy {
+    fn resolve<T: ToFeatureName>(&self, specified: Vec<T>) -> Result<Option<T>, Error> {
+        if specified.len() <= 1 {
+            return Ok(specified.into_iter().next());
+        }
+
+        match self {
+            ExclusiveStrategy::Error => Err(Error::MutualExclusiveFeatureError(
+                specified.into_iter().map(|f| f.to_feature_name()).collect(),
+            )),
+            ExclusiveStrategy::FirstWins => Ok(specified.into_iter().next()),
+            ExclusiveStrategy::Priority(order) => Ok(specified
+                .into_iter()
+                .enumerate()
+                .min_by_key(|(index, feature)| {
+                    let name = feature.to_feature_name();
+                    (
+                        order
+                            .iter()
+                            .position(|prior| prior == &name)
+                            .unwrap_or(order.len()),
+                        *index,
+                    )
+                })
+                .map(|(_, feature)| feature)),
+        }
+    }
+}
+
+/// Policy deciding whether generated features are written back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GenerationPolicy {
+    /// Generate, except while building packaged crate(`cargo package`, `cargo publish`)
+    #[default]
+    Auto,
+    /// Always generate
+    Always,
+    /// Never generate. Committed features are kept as is.
+    Skip,
+}
+
+/// Dependency of generated feature
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum Dependency {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dependency {
+    /// Other feature or optional dependency of the same crate(`feature`)
     Simple(String),
+    /// Feature of other crate(`crate/feature`)
     CrateFeature(String, String),
+    /// Feature of optional crate, without enabling the crate(`crate?/feature`)
     OptionalCrateFeature(String, String),
+    /// Optional dependency, without implicit feature(`dep:crate`)
+    Dep(String),
 }
 
 impl Dependency {
-    fn into_string(self) -> String {
+    /// Other feature or optional dependency of the same crate(`feature`)
+    pub fn simple<S: Into<String>>(feature: S) -> Self {
+        Dependency::Simple(feature.into())
+    }
+
+    /// Feature of other crate(`crate/feature`)
+    pub fn crate_feature<C: Into<String>, F: Into<String>>(crate_name: C, feature: F) -> Self {
+        Dependency::CrateFeature(crate_name.into(), feature.into())
+    }
+
+    /// Feature of optional crate, without enabling the crate(`crate?/feature`)
+    pub fn weak_crate_feature<C: Into<String>, F: Into<String>>(crate_name: C, feature: F) -> Self {
+        Dependency::OptionalCrateFeature(crate_name.into(), feature.into())
+    }
+
+    /// Optional dependency, without implicit feature(`dep:crate`)
+    pub fn dep<C: Into<String>>(crate_name: C) -> Self {
+        Dependency::Dep(crate_name.into())
+    }
+
+    pub(crate) fn into_string(self) -> String {
+        self.to_string()
+    }
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Dependency::Simple(feature) => feature,
-            Dependency::CrateFeature(crate_name, feature) => format!("{}/{}", crate_name, feature),
+            Dependency::Simple(feature) => write!(f, "{}", feature),
+            Dependency::CrateFeature(crate_name, feature) => {
+                write!(f, "{}/{}", crate_name, feature)
+            }
             Dependency::OptionalCrateFeature(crate_name, feature) => {
-                format!("{}?/{}", crate_name, feature)
+                write!(f, "{}?/{}", crate_name, feature)
             }
+            Dependency::Dep(crate_name) => write!(f, "dep:{}", crate_name),
         }
     }
 }
 
+/// Value paired with its feature name
+pub(crate) struct Named<T>(pub(crate) String, pub(crate) T);
+
+impl<T> ToFeatureName for Named<T> {
+    fn to_feature_name(&self) -> String {
+        self.0.clone()
+    }
+}
+
+pub(crate) struct GeneratedFeatures<T> {
+    pub(crate) names: Vec<String>,
+    pub(crate) specified: Vec<T>,
+}
+
 /// This helper provides some safe way to specify dependency of generated feature
-pub struct DependencyHelper<'a>(&'a str, HashSet<Dependency>);
+pub struct DependencyHelper<'a> {
+    feature: &'a str,
+    enabled: bool,
+    dependencies: HashSet<Dependency>,
+    known_features: &'a HashSet<String>,
+    declared_crates: &'a HashMap<String, DeclaredCrate>,
+    path_features: &'a PathFeatures<'a>,
+}
+
+/// Dependency declared in manifest, seen by `DependencyHelper`
+pub(crate) struct DeclaredCrate {
+    optional: bool,
+}
+
+/// Features of path dependencies, read only when propagating to them.\
+/// Failure of reading is kept, so it is reported instead of `DependencyError::UnresolvedPathDependency`.
+pub(crate) struct PathFeatures<'a> {
+    manifest: &'a Manifest,
+    resolved: RefCell<HashMap<String, Option<HashSet<String>>>>,
+    error: RefCell<Option<Error>>,
+}
+
+impl<'a> PathFeatures<'a> {
+    fn new(manifest: &'a Manifest) -> Self {
+        Self {
+            manifest,
+            resolved: Default::default(),
+            error: Default::default(),
+        }
+    }
+
+    /// Whether `crate_name` is path dependency declaring `feature`
+    fn declares(&self, crate_name: &str, feature: &str) -> Result<bool, DependencyError> {
+        if let Some(features) = self.resolved.borrow().get(crate_name) {
+            return Ok(features
+                .as_ref()
+                .is_some_and(|features| features.contains(feature)));
+        }
+        let features = self
+            .manifest
+            .dependency_spec(crate_name)
+            .and_then(|spec| match spec.and_then(|spec| spec.path) {
+                Some(_) => Ok(Some(
+                    self.manifest
+                        .dependency_features(crate_name)?
+                        .into_keys()
+                        .collect::<HashSet<_>>(),
+                )),
+                None => Ok(None),
+            })
+            .map_err(|e| {
+                self.error.borrow_mut().get_or_insert(e);
+                DependencyError::UnresolvedPathDependency
+            })?;
+        let declares = features
+            .as_ref()
+            .is_some_and(|features| features.contains(feature));
+        self.resolved
+            .borrow_mut()
+            .insert(crate_name.to_string(), features);
+        Ok(declares)
+    }
+
+    fn take_error(&self) -> Option<Error> {
+        self.error.borrow_mut().take()
+    }
+}
+
+/// Context of feature being generated, exposed to dependency setter
+pub struct FeatureContext<'a> {
+    name: &'a str,
+    enabled: bool,
+    declared_crates: &'a HashMap<String, DeclaredCrate>,
+}
+
+impl<'a> FeatureContext<'a> {
+    /// Final name of feature
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Whether feature is enabled in current build
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Dependencies declared in manifest, with whether each is optional. Sorted by name.
+    pub fn dependencies(&self) -> Vec<(&str, bool)> {
+        let mut dependencies = self
+            .declared_crates
+            .iter()
+            .map(|(name, declared)| (name.as_str(), declared.optional))
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        dependencies
+    }
+
+    /// Whether dependency is declared in manifest
+    pub fn has_dependency(&self, crate_name: &str) -> bool {
+        self.declared_crates.contains_key(crate_name)
+    }
+
+    /// Whether dependency is declared in manifest as optional
+    pub fn is_optional_dependency(&self, crate_name: &str) -> bool {
+        self.declared_crates
+            .get(crate_name)
+            .map(|declared| declared.optional)
+            .unwrap_or_default()
+    }
+}
 
 /// Possible dependency error from `DependencyHelper`
 #[derive(thiserror::Error, Debug, Clone, Copy)]
@@ -50,19 +361,73 @@ pub enum DependencyError {
     Conflict,
     #[error("Invalid dependency format")]
     InvalidDependencyFormat,
+    #[error("Feature is neither declared nor generated")]
+    UnknownFeature,
+    #[error("Cannot read features of path dependency")]
+    UnresolvedPathDependency,
 }
 
 impl<'a> DependencyHelper<'a> {
+    /// Context of feature being generated
+    pub fn context(&self) -> FeatureContext<'_> {
+        FeatureContext {
+            name: self.feature,
+            enabled: self.enabled,
+            declared_crates: self.declared_crates,
+        }
+    }
+
     /// propagate feature to other crate
     pub fn propagate_to_crate(
         &mut self,
         crate_name: &str,
         optional: bool,
     ) -> Result<(), DependencyError> {
-        self.add_crate_feature_dependency(crate_name, self.0, optional)
+        self.add_crate_feature_dependency(crate_name, self.feature, optional)
+    }
+
+    /// propagate feature to other crate, only when the crate is declared as dependency
+    pub fn propagate_to_crate_if_present(
+        &mut self,
+        crate_name: &str,
+        optional: bool,
+    ) -> Result<(), DependencyError> {
+        if self.declared_crates.contains_key(crate_name) {
+            self.propagate_to_crate(crate_name, optional)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// propagate feature to every path dependency declaring the same feature, except `exclude`.\
+    /// Optional dependencies are propagated weakly(`crate?/feature`).
+    pub fn propagate_to_all_path_deps(&mut self, exclude: &[&str]) -> Result<(), DependencyError> {
+        let mut targets = Vec::new();
+        for (name, declared) in self.declared_crates {
+            if !exclude.contains(&name.as_str())
+                && self.path_features.declares(name, self.feature)?
+            {
+                targets.push((name.as_str(), declared.optional));
+            }
+        }
+        targets.sort();
+        targets
+            .into_iter()
+            .try_for_each(|(crate_name, optional)| self.propagate_to_crate(crate_name, optional))
+    }
+
+    /// propagate feature to multiple crates
+    pub fn propagate_to_crates<'c, I: IntoIterator<Item = &'c str>>(
+        &mut self,
+        crate_names: I,
+        optional: bool,
+    ) -> Result<(), DependencyError> {
+        crate_names
+            .into_iter()
+            .try_for_each(|crate_name| self.propagate_to_crate(crate_name, optional))
     }
 
-    fn add_crate_feature_dependency(
+    pub(crate) fn add_crate_feature_dependency(
         &mut self,
         crate_name: &str,
         feature_name: &str,
@@ -74,10 +439,10 @@ impl<'a> DependencyHelper<'a> {
         } else {
             Dependency::OptionalCrateFeature(crate_name, feature_name)
         };
-        if self.1.contains(&conflict) {
+        if self.dependencies.contains(&conflict) {
             Err(DependencyError::Conflict)
         } else {
-            self.1.insert(match conflict {
+            self.dependencies.insert(match conflict {
                 Dependency::OptionalCrateFeature(crate_name, feature_name) => {
                     Dependency::CrateFeature(crate_name, feature_name)
                 }
@@ -90,6 +455,56 @@ impl<'a> DependencyHelper<'a> {
         }
     }
 
+    /// Require other feature of the same crate.\
+    /// Feature should be declared in manifest, generated, or be a declared group feature.
+    pub fn enable_feature<N: AsRef<str>>(&mut self, feature: N) -> Result<(), DependencyError> {
+        let feature = feature.as_ref();
+        if self.known_features.contains(feature) {
+            self.dependencies
+                .insert(Dependency::Simple(feature.to_string()));
+            Ok(())
+        } else {
+            Err(DependencyError::UnknownFeature)
+        }
+    }
+
+    /// Dependencies added so far, in no particular order
+    pub fn entries(&self) -> impl Iterator<Item = &Dependency> {
+        self.dependencies.iter()
+    }
+
+    /// Add typed dependency
+    pub fn add(&mut self, dependency: Dependency) -> Result<(), DependencyError> {
+        match dependency {
+            Dependency::CrateFeature(crate_name, feature_name) => {
+                self.add_crate_feature_dependency(&crate_name, &feature_name, false)
+            }
+            Dependency::OptionalCrateFeature(crate_name, feature_name) => {
+                self.add_crate_feature_dependency(&crate_name, &feature_name, true)
+            }
+            dependency => {
+                self.dependencies.insert(dependency);
+                Ok(())
+            }
+        }
+    }
+
+    /// Retract previously added dependency.\
+    /// Returns whether the dependency was present.
+    pub fn remove(&mut self, dependency: &Dependency) -> bool {
+        self.dependencies.remove(dependency)
+    }
+
+    /// add multiple dependencies for feature
+    pub fn add_dependencies<'d, I: IntoIterator<Item = &'d str>>(
+        &mut self,
+        dependency_names: I,
+    ) -> Result<(), DependencyError> {
+        dependency_names
+            .into_iter()
+            .try_for_each(|dependency_name| self.add_dependency(dependency_name))
+    }
+
     // add dependency for feature
     pub fn add_dependency(&mut self, dependency_name: &str) -> Result<(), DependencyError> {
         if dependency_name.contains('/') {
@@ -103,81 +518,303 @@ impl<'a> DependencyHelper<'a> {
             if splitted_dependency_name.next().is_some() {
                 Err(DependencyError::InvalidDependencyFormat)
             } else {
-                let (crate_name, optional) = if crate_name.ends_with('?') {
-                    (&crate_name[0..(crate_name.len() - 2)], true)
-                } else {
-                    (crate_name, false)
+                let (crate_name, optional) = match crate_name.strip_suffix('?') {
+                    Some(crate_name) => (crate_name, true),
+                    None => (crate_name, false),
                 };
                 self.add_crate_feature_dependency(crate_name, feature_name, optional)
             }
+        } else if let Some(crate_name) = dependency_name.strip_prefix("dep:") {
+            self.dependencies
+                .insert(Dependency::Dep(crate_name.to_string()));
+            Ok(())
         } else {
-            self.1
+            self.dependencies
                 .insert(Dependency::Simple(dependency_name.to_string()));
             Ok(())
         }
     }
 }
 
-const FEATURES_TABLE_NAME: &str = "features";
-const AUTO_GENERATE_COMMENT: &str = concat!(" # auto-generated by ", env!("CARGO_CRATE_NAME"));
+pub(crate) fn insert_generated_feature(
+    features: &mut Table,
+    name: &str,
+    dependencies: Vec<String>,
+) {
+    insert_annotated_feature(features, name, dependencies, None)
+}
+
+/// Insert generated feature, with annotation(e.g. `group=backends`) following the marker comment
+pub(crate) fn insert_annotated_feature(
+    features: &mut Table,
+    name: &str,
+    mut dependencies: Vec<String>,
+    group: Option<&str>,
+) {
+    dependencies.sort();
+    let mut array = Array::from_iter(
+        dependencies
+            .into_iter()
+            .map(|dep| Value::String(Formatted::<String>::new(dep))),
+    );
+    match group {
+        Some(group) => array.decor_mut().set_suffix(format!(
+            "{}{}{}{}",
+            AUTO_GENERATE_COMMENT, ANNOTATION_SEPARATOR, GROUP_ANNOTATION, group
+        )),
+        None => array.decor_mut().set_suffix(AUTO_GENERATE_COMMENT),
+    }
+    features.insert(name, Item::Value(Value::Array(array)));
+}
+
+/// Whether feature is marked as generated, with annotated group if any
+pub(crate) fn generation_mark(item: &Item) -> Option<Option<String>> {
+    let comment = item
+        .as_array()?
+        .decor()
+        .suffix()
+        .and_then(|s| s.as_str())
+        .unwrap_or_default()
+        .trim();
+    let annotation = comment.strip_prefix(AUTO_GENERATE_COMMENT.trim())?;
+    if annotation.is_empty() {
+        Some(None)
+    } else {
+        annotation
+            .strip_prefix(ANNOTATION_SEPARATOR.trim_end())
+            .map(|annotation| {
+                annotation
+                    .trim()
+                    .strip_prefix(GROUP_ANNOTATION)
+                    .map(str::to_string)
+            })
+    }
+}
+
+pub(crate) const FEATURES_TABLE_NAME: &str = "features";
+pub(crate) const AUTO_GENERATE_COMMENT: &str =
+    concat!(" # auto-generated by ", env!("CARGO_CRATE_NAME"));
+const ANNOTATION_SEPARATOR: &str = "; ";
+const GROUP_ANNOTATION: &str = "group=";
+const VCS_INFO_FILE_NAME: &str = ".cargo_vcs_info.json";
+const ORIGINAL_MANIFEST_FILE_NAME: &str = "Cargo.toml.orig";
 
 impl Manifest {
     /// Load cargo manifest from specified path
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.display()))
+    )]
     pub fn new(path: PathBuf, prevent_build_when_changed: bool) -> Result<Self, Error> {
-        let document = std::fs::read_to_string(&path)?;
-        let mut document: toml_edit::DocumentMut = document.parse()?;
+        let source =
+            std::fs::read_to_string(&path).map_err(|e| Error::from(e).at_manifest(&path))?;
+        Self::from_source(path, &source, prevent_build_when_changed)
+    }
 
-        let original_features = Self::collect_features(&document)?;
+    /// Load cargo manifest from `source`, which is written to `path`
+    pub(crate) fn from_source(
+        path: PathBuf,
+        source: &str,
+        prevent_build_when_changed: bool,
+    ) -> Result<Self, Error> {
+        let source_path = path.clone();
+        Self::from_source_at(path, &source_path, source, prevent_build_when_changed)
+    }
+
+    /// Same as `from_source`, but `source` is read from `source_path`(e.g. template), which errors refer
+    pub(crate) fn from_source_at(
+        path: PathBuf,
+        source_path: &Path,
+        source: &str,
+        prevent_build_when_changed: bool,
+    ) -> Result<Self, Error> {
+        let line_ending = LineEnding::detect(source);
+        let document = ImDocument::parse(source)
+            .map_err(|e| Error::from(e).parsing_manifest(source_path, source))?;
+
+        let original_features =
+            Self::collect_features(source_path, document.as_table(), Some(source))?;
+        let mut document = document.into_mut();
+        let original_content = Self::content_without_features(&document);
 
         let table = document.as_table_mut();
         if !table.contains_key(FEATURES_TABLE_NAME) {
             table.insert(FEATURES_TABLE_NAME, Item::Table(Table::new()));
         }
 
+        let previous_state = GenerationState::read(source_path, &document)?;
+        let read_only = Self::detect_read_only(&path);
+        let packaged = Self::detect_packaged(&path);
+
         let mut ret = Self {
             path,
             original_features,
             document,
             prevent_build_when_changed,
+            read_only,
+            packaged,
+            generation_policy: Default::default(),
+            line_ending,
+            exclusion_groups: Default::default(),
+            anonymous_exclusion_groups: Default::default(),
+            generated_features: Default::default(),
+            tiers: Default::default(),
+            parent_features: Default::default(),
+            aliases: Default::default(),
+            deprecated_features: Default::default(),
+            adopt_policy: Default::default(),
+            original_content,
+            previous_generated: Default::default(),
+            regeneration_policy: Default::default(),
+            group_regeneration_policies: Default::default(),
+            generated_dependencies: Default::default(),
+            optional_dependencies: previous_state
+                .iter()
+                .flat_map(|state| state.optional_dependencies.iter().cloned())
+                .collect(),
+            annotate_groups: false,
+            previous_groups: Default::default(),
+            record_state: false,
+            previous_state,
+            lock_path: None,
+            pinned_features: Default::default(),
+            hooks: Default::default(),
+            feature_source: Box::new(EnvFeatureSource::cargo()),
+            exports: Default::default(),
+            template_path: None,
+            maintain_check_cfg: false,
+            patches: Default::default(),
+            rust_versions: Default::default(),
+            native_probe: Default::default(),
         };
 
         ret.clear_generated_features()?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            features = ret.original_features.len(),
+            previously_generated = ret.previous_generated.len(),
+            "manifest loaded"
+        );
 
         Ok(ret)
     }
 
-    /// Load cargo manifest of current crate
+    /// Load cargo manifest of current crate.\
+    /// Manifest of other crate is loaded instead when `MANIFEST_FEATURE_GEN_MANIFEST` is set.
     pub fn new_with_env(prevent_build_when_changed: bool) -> Result<Self, Error> {
-        let mut path: PathBuf = std::env::var("CARGO_MANIFEST_DIR")
-            .map_err(|_| Error::EnvError)?
-            .into();
-        path.push("Cargo.toml");
-        Self::new(path, prevent_build_when_changed)
-    }
-
-    fn collect_features(document: &DocumentMut) -> Result<HashMap<String, HashSet<String>>, Error> {
-        if let Some(features) = document.as_table().get(FEATURES_TABLE_NAME) {
-            let features = features
-                .as_table()
-                .ok_or_else(|| Error::MalformedManifest("features is not a table".to_string()))?;
+        Self::new(crate::env::manifest_path()?, prevent_build_when_changed)
+    }
+
+    /// Same as `new_with_env`, but `manifest_override`(manifest path or its directory) takes precedence if given.\
+    /// Useful for xtask-style tools generating features for other crate.
+    pub fn new_with_override(
+        manifest_override: Option<PathBuf>,
+        prevent_build_when_changed: bool,
+    ) -> Result<Self, Error> {
+        match manifest_override {
+            Some(path) => Self::new(
+                crate::env::manifest_path_of(path),
+                prevent_build_when_changed,
+            ),
+            None => Self::new_with_env(prevent_build_when_changed),
+        }
+    }
+
+    /// Path of loaded manifest
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Packaged crates(registry or vendored checkouts) contain `.cargo_vcs_info.json`.
+    /// Such manifests and read-only files must not be rewritten.
+    fn detect_read_only(path: &Path) -> bool {
+        let packaged = path
+            .parent()
+            .map(|dir| dir.join(VCS_INFO_FILE_NAME).exists())
+            .unwrap_or_default();
+        let read_only = std::fs::metadata(path)
+            .map(|metadata| metadata.permissions().readonly())
+            .unwrap_or_default();
+        packaged || read_only
+    }
+
+    /// Whether manifest is verified only, without writing back.
+    ///
+    /// This is detected while loading for packaged crates and read-only files.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// `cargo package` and `cargo publish` build the crate from packaged sources,
+    /// which always contain `Cargo.toml.orig` next to the normalized manifest.
+    fn detect_packaged(path: &Path) -> bool {
+        path.parent()
+            .map(|dir| dir.join(ORIGINAL_MANIFEST_FILE_NAME).exists())
+            .unwrap_or_default()
+    }
+
+    /// Whether manifest is loaded from packaged sources
+    pub fn is_packaged(&self) -> bool {
+        self.packaged
+    }
+
+    /// Set policy deciding whether generated features are written back
+    pub fn set_generation_policy(&mut self, policy: GenerationPolicy) {
+        self.generation_policy = policy;
+    }
+
+    fn should_generate(&self) -> bool {
+        match self.generation_policy {
+            GenerationPolicy::Auto => !self.packaged,
+            GenerationPolicy::Always => true,
+            GenerationPolicy::Skip => false,
+        }
+    }
+
+    pub(crate) fn collect_features(
+        path: &Path,
+        document: &Table,
+        source: Option<&str>,
+    ) -> Result<HashMap<String, HashSet<String>>, Error> {
+        if let Some(features) = document.get(FEATURES_TABLE_NAME) {
+            let features = features.as_table().ok_or_else(|| {
+                Error::malformed(
+                    path,
+                    &[FEATURES_TABLE_NAME],
+                    ItemKind::Table,
+                    ItemKind::of_item(features),
+                    SourceLocation::locate(source, features.span()),
+                )
+            })?;
             fallible_iterator::convert(features.into_iter().map(
                 |(feature, deps)| -> Result<_, Error> {
                     let deps = deps.as_array().ok_or_else(|| {
-                        Error::MalformedManifest(format!("feature({}) is not a array", feature))
+                        Error::malformed(
+                            path,
+                            &[FEATURES_TABLE_NAME, feature],
+                            ItemKind::Array,
+                            ItemKind::of_item(deps),
+                            SourceLocation::locate(source, deps.span()),
+                        )
                     })?;
 
                     Ok((
                         feature.to_string(),
-                        fallible_iterator::convert(deps.into_iter().map(|dep| {
-                            dep.as_str()
-                                .ok_or_else(|| {
-                                    Error::MalformedManifest(format!(
-                                        "feature({}) has non string item as dependency",
-                                        feature
-                                    ))
-                                })
-                                .map(|dep| dep.to_string())
-                        }))
+                        fallible_iterator::convert(deps.into_iter().enumerate().map(
+                            |(index, dep)| {
+                                dep.as_str()
+                                    .ok_or_else(|| {
+                                        Error::malformed(
+                                            path,
+                                            &[FEATURES_TABLE_NAME, feature, &index.to_string()],
+                                            ItemKind::String,
+                                            ItemKind::of_value(dep),
+                                            SourceLocation::locate(source, dep.span()),
+                                        )
+                                    })
+                                    .map(|dep| dep.to_string())
+                            },
+                        ))
                         .collect::<HashSet<_>>()?,
                     ))
                 },
@@ -189,31 +826,56 @@ impl Manifest {
     }
 
     fn clear_generated_features(&mut self) -> Result<(), Error> {
+        let owned_by_state = self
+            .previous_state
+            .as_ref()
+            .map(|state| state.features.iter().cloned().collect::<HashSet<_>>())
+            .unwrap_or_default();
         if let Some(features) = self.document.as_table_mut().get_mut(FEATURES_TABLE_NAME) {
-            let features = features
-                .as_table_mut()
-                .ok_or_else(|| Error::MalformedManifest("features is not a table".to_string()))?;
+            let found = ItemKind::of_item(features);
+            let features = features.as_table_mut().ok_or_else(|| {
+                Error::malformed(
+                    &self.path,
+                    &[FEATURES_TABLE_NAME],
+                    ItemKind::Table,
+                    found,
+                    None,
+                )
+            })?;
             let feature_names =
                 fallible_iterator::convert(features.iter().filter_map(|(feature, item)| {
-                    if let Some(deps) = item.as_array() {
-                        (deps
-                            .decor()
-                            .suffix()
-                            .and_then(|s| s.as_str())
-                            .unwrap_or_default()
-                            .trim()
-                            == AUTO_GENERATE_COMMENT.trim())
-                        .then(|| Ok(feature.to_string()))
+                    if item.is_array() {
+                        match generation_mark(item) {
+                            Some(group) => Some(Ok((feature.to_string(), group))),
+                            None => owned_by_state
+                                .contains(feature)
+                                .then(|| Ok((feature.to_string(), None))),
+                        }
                     } else {
-                        Some(Err(Error::MalformedManifest(format!(
-                            "value of feature({}) is not a array",
-                            feature
-                        ))))
+                        Some(Err(Error::malformed(
+                            &self.path,
+                            &[FEATURES_TABLE_NAME, feature],
+                            ItemKind::Array,
+                            ItemKind::of_item(item),
+                            None,
+                        )))
                     }
                 }))
                 .collect::<Vec<_>>()?;
-            for feature in feature_names {
-                features.remove(&feature);
+            for (feature, group) in feature_names {
+                if let Some(group) = group {
+                    self.previous_groups.insert(feature.clone(), group);
+                }
+                if let Some(deps) = features.remove(&feature) {
+                    let deps = deps
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect();
+                    self.previous_generated.insert(feature, deps);
+                }
             }
         }
 
@@ -229,48 +891,701 @@ impl Manifest {
         &mut self,
         feature_names: I,
         dependency_setter: F,
-    ) -> Result<Vec<T>, Error> {
-        let table = self.document.as_table_mut();
-        let features = table.get_mut(FEATURES_TABLE_NAME).unwrap();
-        let features = features.as_table_mut().unwrap();
+    ) -> Result<FeatureSet<T>, Error> {
+        Ok(self
+            .generate_features(feature_names, dependency_setter)?
+            .specified
+            .into_iter()
+            .collect())
+    }
+
+    /// Add every variant of `T` to manifest. Same as `add_features` with `T::all_variants()`.
+    pub fn add_all_variants<
+        T: ToFeatureName + EnumerateFeatures,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        self.add_features(T::all_variants().into_iter(), dependency_setter)
+    }
+
+    /// Add feature to manifest, with fallible dependency setter.\
+    /// Generation is aborted with `Error::InvalidDependency` naming the feature, when setter fails.
+    pub fn try_add_features<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>) -> Result<(), DependencyError>,
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        Ok(self
+            .try_generate_features(None, feature_names, dependency_setter)?
+            .specified
+            .into_iter()
+            .collect())
+    }
+
+    /// Add feature to manifest, named with `prefix`(e.g. `backend-`) followed by `ToFeatureName`.\
+    /// Enabled features are returned.
+    pub fn add_features_with_prefix<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        prefix: &str,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        Ok(FeatureSet::from_named(
+            self.generate_features_with_prefix(prefix, None, feature_names, dependency_setter)?
+                .specified,
+        ))
+    }
+
+    pub(crate) fn generate_features_with_prefix<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        prefix: &str,
+        group: Option<&str>,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<GeneratedFeatures<Named<T>>, Error> {
+        let feature_names = feature_names
+            .map(|feature| Named(format!("{}{}", prefix, feature.to_feature_name()), feature));
+        let dependency_setter = |feature: &Named<T>, helper: &mut DependencyHelper<'_>| {
+            dependency_setter(&feature.1, helper)
+        };
+        match group {
+            Some(group) => self.generate_features_in_group(group, feature_names, dependency_setter),
+            None => self.generate_features(feature_names, dependency_setter),
+        }
+    }
+
+    /// Add feature to manifest, named by `formatter` instead of `ToFeatureName`.\
+    /// See `Formatter` for ready-made formatters.
+    pub fn add_features_with_formatter<
+        T,
+        I: Iterator<Item = T>,
+        R: IntoFeatureName,
+        N: Fn(&T) -> R,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        formatter: N,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        let features = feature_names
+            .map(|feature| {
+                let name = formatter(&feature).into_feature_name()?;
+                Ok(Named(name.into_string(), feature))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(FeatureSet::from_named(
+            self.generate_features(features.into_iter(), |feature, helper| {
+                dependency_setter(&feature.1, helper)
+            })?
+            .specified,
+        ))
+    }
+
+    fn declared_crates(&self) -> HashMap<String, DeclaredCrate> {
+        self.dependency_entries()
+            .into_iter()
+            .map(|(name, item)| {
+                (
+                    name.to_string(),
+                    DeclaredCrate {
+                        optional: crate::dependencies::is_optional_entry(item),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Set source deciding which features are enabled. `CARGO_FEATURE_*` variables are used by default.
+    pub fn set_feature_source<S: FeatureSource + 'static>(&mut self, source: S) {
+        self.feature_source = Box::new(source);
+    }
+
+    /// Annotate features generated as members of named group with trailing comment(e.g. `group=backends`).\
+    /// Disabled by default.
+    pub fn set_annotate_groups(&mut self, annotate: bool) {
+        self.annotate_groups = annotate;
+    }
+
+    /// Group of previously generated feature, recorded by annotation comment
+    pub fn previous_group_of(&self, feature: &str) -> Option<&str> {
+        self.previous_groups.get(feature).map(String::as_str)
+    }
+
+    /// Record generation state under `[package.metadata.manifest-feature-gen]` while writing.\
+    /// Recorded features are recognized as generated even when marker comments are stripped. Disabled by default.
+    pub fn set_record_state(&mut self, record: bool) {
+        self.record_state = record;
+    }
+
+    /// Generation state recorded by previous run
+    pub fn previous_state(&self) -> Option<&GenerationState> {
+        self.previous_state.as_ref()
+    }
+
+    /// Whether generated features are edited after previous run recorded its state.\
+    /// State recorded without hash is never drifted.
+    pub fn is_state_drifted(&self) -> bool {
+        self.previous_state
+            .as_ref()
+            .filter(|state| !state.hash.is_empty())
+            .map(|state| {
+                let previous = self
+                    .previous_generated
+                    .iter()
+                    .map(|(feature, deps)| (feature.as_str(), deps.as_slice()));
+                state.hash != GenerationState::hash_of(previous)
+            })
+            .unwrap_or_default()
+    }
+
+    fn store_state(&mut self) {
+        let features = self.document[FEATURES_TABLE_NAME].as_table().unwrap();
+        let generated = features
+            .iter()
+            .filter(|(feature, item)| {
+                generation_mark(item).is_some()
+                    || self.generated_features.iter().any(|f| f == feature)
+            })
+            .map(|(feature, item)| {
+                (
+                    feature.to_string(),
+                    item.as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let groups = self
+            .exclusion_groups
+            .iter()
+            .map(|(group, exclusion_group)| (group.clone(), exclusion_group.members.clone()))
+            .chain(self.parent_features.clone())
+            .collect();
+        let state = GenerationState {
+            groups,
+            features: generated
+                .iter()
+                .map(|(feature, _)| feature.clone())
+                .collect(),
+            hash: GenerationState::hash_of(
+                generated
+                    .iter()
+                    .map(|(feature, deps)| (feature.as_str(), deps.as_slice())),
+            ),
+            dependencies: generated
+                .iter()
+                .filter_map(|(feature, _)| {
+                    let deps = self.generated_dependencies.get(feature)?;
+                    Some((feature.clone(), deps.clone()))
+                })
+                .collect(),
+            optional_dependencies: self.optional_dependencies.iter().cloned().collect(),
+        };
+        state.write(&mut self.document);
+    }
+
+    /// Set policy for features already written by hand, claimed by following add calls.\
+    /// By default, dependencies of them are replaced.
+    pub fn set_adopt_policy(&mut self, policy: MergePolicy) {
+        self.adopt_policy = policy;
+    }
+
+    /// Set policy for previously generated features, regenerated by following add calls.\
+    /// Dependencies neither generated this time nor by previous run are considered as manually added ones.
+    /// Dependencies generated by previous run are known only when state is recorded(`set_record_state`),
+    /// otherwise every dependency not generated this time is considered as manual one.\
+    /// By default, dependencies of them are replaced.
+    pub fn set_regeneration_policy(&mut self, policy: MergePolicy) {
+        self.regeneration_policy = policy;
+    }
+
+    /// Set regeneration policy for members of `group`, overriding `set_regeneration_policy`
+    pub fn set_group_regeneration_policy(&mut self, group: &str, policy: MergePolicy) {
+        self.group_regeneration_policies
+            .insert(group.to_string(), policy);
+    }
+
+    pub(crate) fn generate_features<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<GeneratedFeatures<T>, Error> {
+        self.try_generate_features(None, feature_names, |feature, helper| {
+            dependency_setter(feature, helper);
+            Ok(())
+        })
+    }
 
-        let mut specified_features = Vec::new();
+    /// Same as `generate_features`, but features are annotated as members of `group`
+    pub(crate) fn generate_features_in_group<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        group: &str,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<GeneratedFeatures<T>, Error> {
+        self.try_generate_features(Some(group), feature_names, |feature, helper| {
+            dependency_setter(feature, helper);
+            Ok(())
+        })
+        .map_err(|e| e.in_group(group))
+    }
 
-        for feature in feature_names {
-            let feature_name = feature.to_feature_name();
-            let mut propagator = DependencyHelper(&feature_name, Default::default());
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(group = ?group)))]
+    pub(crate) fn try_generate_features<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>) -> Result<(), DependencyError>,
+    >(
+        &mut self,
+        group: Option<&str>,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<GeneratedFeatures<T>, Error> {
+        let annotation = group.filter(|_| self.annotate_groups);
+        let regeneration_policy = group
+            .and_then(|group| self.group_regeneration_policies.get(group))
+            .copied()
+            .unwrap_or(self.regeneration_policy);
+        let declared_crates = self.declared_crates();
+        let features = self.document[FEATURES_TABLE_NAME].as_table().unwrap();
+
+        let feature_names = feature_names
+            .map(|feature| (feature.to_feature_name(), feature))
+            .collect::<Vec<_>>();
+        for (name, _) in &feature_names {
+            if self.pinned_features.contains(name) {
+                return Err(Error::PinnedFeature(name.clone()));
+            }
+            crate::FeatureName::new(name.as_str()).map_err(|source| Error::InvalidFeatureName {
+                name: name.clone(),
+                group: None,
+                source,
+            })?;
+        }
+        let known_features = features
+            .iter()
+            .map(|(feature, _)| feature.to_string())
+            .chain(feature_names.iter().map(|(name, _)| name.clone()))
+            .chain(self.parent_features.keys().cloned())
+            .chain(self.tiers.iter().map(|(name, _)| name.clone()))
+            .collect::<HashSet<_>>();
+
+        let path_features = PathFeatures::new(self);
+        let mut resolved = Vec::new();
+        for (feature_name, feature) in feature_names {
+            let enabled = self.feature_source.is_enabled(&feature_name);
+            let mut propagator = DependencyHelper {
+                feature: &feature_name,
+                enabled,
+                dependencies: Default::default(),
+                known_features: &known_features,
+                declared_crates: &declared_crates,
+                path_features: &path_features,
+            };
             let manual_dependent_feature = format!("__{}", feature_name);
             if features.contains_key(&manual_dependent_feature) {
                 propagator
-                    .1
+                    .dependencies
                     .insert(Dependency::Simple(manual_dependent_feature));
             }
-            dependency_setter(&feature, &mut propagator);
+            let result = dependency_setter(&feature, &mut propagator);
+            if let Some(e) = path_features.take_error() {
+                return Err(e);
+            }
+            result.map_err(|source| Error::InvalidDependency {
+                feature: feature_name.clone(),
+                source,
+            })?;
             let mut dependencies = propagator
-                .1
+                .dependencies
                 .into_iter()
                 .map(Dependency::into_string)
                 .collect::<Vec<_>>();
             dependencies.sort();
-            let mut array = Array::from_iter(
-                dependencies
-                    .into_iter()
-                    .map(|dep| Value::String(Formatted::<String>::new(dep))),
-            );
-            array.decor_mut().set_suffix(AUTO_GENERATE_COMMENT);
-            features.insert(&feature_name, Item::Value(Value::Array(array)));
-
-            if std::env::var(format!(
-                "CARGO_FEATURE_{}",
-                feature_name.replace('-', "_").to_uppercase()
-            ))
-            .is_ok()
+            let generated_dependencies = dependencies.clone();
+            let dependencies = match features.get(&feature_name).and_then(Item::as_array) {
+                Some(existing) => self.adopt_policy.merge(
+                    &feature_name,
+                    existing
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect(),
+                    dependencies,
+                )?,
+                None => match self.previous_generated.get(&feature_name) {
+                    Some(previous) => {
+                        // dependencies generated by previous run are stale, not manual
+                        let stale = self
+                            .previous_state
+                            .as_ref()
+                            .and_then(|state| state.dependencies.get(&feature_name));
+                        let previous = previous
+                            .iter()
+                            .filter(|dep| !stale.is_some_and(|stale| stale.contains(dep)))
+                            .cloned()
+                            .collect();
+                        regeneration_policy.merge(&feature_name, previous, dependencies)?
+                    }
+                    None => dependencies,
+                },
+            };
+            resolved.push((
+                feature_name,
+                feature,
+                enabled,
+                dependencies,
+                generated_dependencies,
+            ));
+        }
+
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        let mut generated = GeneratedFeatures {
+            names: Vec::new(),
+            specified: Vec::new(),
+        };
+        for (feature_name, feature, enabled, dependencies, generated_dependencies) in resolved {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(feature = %feature_name, ?dependencies, "feature generated");
+            insert_annotated_feature(features, &feature_name, dependencies, annotation);
+            generated.names.push(feature_name.clone());
+            self.generated_dependencies
+                .insert(feature_name.clone(), generated_dependencies);
+            self.generated_features.push(feature_name);
+            if enabled {
+                generated.specified.push(feature);
+            }
+        }
+
+        Ok(generated)
+    }
+
+    /// Add feature for each combination of `a` and `b` to manifest.\
+    /// Name of each feature is made by `formatter`. Enabled combinations are returned.
+    pub fn add_feature_product<
+        A: Clone,
+        B: Clone,
+        IA: Iterator<Item = A>,
+        IB: Iterator<Item = B>,
+        R: IntoFeatureName,
+        N: Fn(&A, &B) -> R,
+        F: Fn(&A, &B, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        a: IA,
+        b: IB,
+        formatter: N,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<(A, B)>, Error> {
+        let b = b.collect::<Vec<_>>();
+        let product = a
+            .flat_map(|a| {
+                b.iter()
+                    .map(|b| {
+                        let name = formatter(&a, b).into_feature_name()?;
+                        Ok(Named(name.into_string(), (a.clone(), b.clone())))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(FeatureSet::from_named(
+            self.add_features(product.into_iter(), |feature, helper| {
+                dependency_setter(&feature.1 .0, &feature.1 .1, helper)
+            })?
+            .into_vec(),
+        ))
+    }
+
+    /// Add feature named `{prefix}-{value}` for each value(e.g. `channels-2`, `channels-4`) to manifest.\
+    /// Values of enabled features are returned.
+    pub fn add_value_features<
+        V: std::fmt::Display,
+        I: Iterator<Item = V>,
+        F: Fn(&V, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        prefix: &str,
+        values: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<V>, Error> {
+        Ok(FeatureSet::from_named(
+            self.add_features(
+                values.map(|value| Named(format!("{}-{}", prefix, value), value)),
+                |feature, helper| dependency_setter(&feature.1, helper),
+            )?
+            .into_vec(),
+        ))
+    }
+
+    /// Add features defined in external TOML file to manifest. See `load_feature_definitions`.\
+    /// Enabled features are returned.
+    pub fn add_features_from_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<FeatureSet<FeatureDefinition>, Error> {
+        self.add_feature_definitions(load_feature_definitions(path)?)
+    }
+
+    /// Add features of loaded definitions(e.g. `read_feature_definitions`). Enabled features are returned.
+    pub fn add_feature_definitions(
+        &mut self,
+        definitions: Vec<FeatureDefinition>,
+    ) -> Result<FeatureSet<FeatureDefinition>, Error> {
+        self.try_add_features(definitions.into_iter(), |definition, helper| {
+            helper.add_dependencies(definition.dependencies.iter().map(String::as_str))
+        })
+    }
+
+    /// Keep alias feature(`old = ["new"]`) for each renamed feature, during deprecation window.\
+    /// `cargo:warning` is emitted for each enabled alias, and enabled aliases are returned.
+    /// Fails with `Error::InvalidFeatureName` when old name is not valid feature name.
+    pub fn add_feature_aliases<'a, I: Iterator<Item = (&'a str, &'a str)>>(
+        &mut self,
+        migrations: I,
+    ) -> Result<Vec<String>, Error> {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        let mut specified = Vec::new();
+        for (old, new) in migrations {
+            let old = crate::FeatureName::validated(old.to_string())?;
+            let old = old.as_str();
+            if features.contains_key(old) {
+                return Err(Error::FeatureAlreadyExists(old.to_string()));
+            }
+            insert_generated_feature(features, old, vec![new.to_string()]);
+            self.aliases.push((old.to_string(), new.to_string()));
+
+            if self.feature_source.is_enabled(old) {
+                println!(
+                    "cargo:warning=feature({}) is renamed to {}. Please use new name",
+                    old, new
+                );
+                specified.push(old.to_string());
+            }
+        }
+
+        Ok(specified)
+    }
+
+    /// Alias features added by `add_feature_aliases`, as `(old, new)`
+    pub fn feature_aliases(&self) -> &[(String, String)] {
+        &self.aliases
+    }
+
+    /// Underlying document being edited
+    pub fn document(&self) -> &toml_edit::DocumentMut {
+        &self.document
+    }
+
+    /// **Advanced API**: underlying document, for edits adjacent to features(metadata tables, package fields, etc.)
+    ///
+    /// Changes are detected and written back by `write`, same as features.
+    /// Modifying `[features]` directly can confuse generation. Do not remove auto-generated marking comment.
+    pub fn document_mut(&mut self) -> &mut toml_edit::DocumentMut {
+        &mut self.document
+    }
+
+    /// Remove feature from manifest, with references from other features.\
+    /// Return whether feature existed.
+    pub fn remove_feature(&mut self, name: &str) -> bool {
+        !self
+            .remove_features_matching(|feature| feature == name)
+            .is_empty()
+    }
+
+    /// Pin features, which are never rewritten nor removed by this crate.\
+    /// Pinned features removed while loading as previously generated ones are restored as is.\
+    /// Add calls claiming pinned feature fail with `Error::PinnedFeature`.
+    pub fn pin_features<'a, I: IntoIterator<Item = &'a str>>(&mut self, names: I) {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        for name in names {
+            if !features.contains_key(name) {
+                if let Some(deps) = self.previous_generated.get(name) {
+                    insert_annotated_feature(
+                        features,
+                        name,
+                        deps.clone(),
+                        self.previous_groups.get(name).map(String::as_str),
+                    );
+                }
+            }
+            self.pinned_features.insert(name.to_string());
+        }
+    }
+
+    /// Whether feature is pinned
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned_features.contains(name)
+    }
+
+    /// Remove features matching `predicate` from manifest, with references from other features,
+    /// groups, parent features and tiers.\
+    /// Pinned features are neither removed nor modified.\
+    /// Names of removed features are returned.
+    pub fn remove_features_matching<P: Fn(&str) -> bool>(&mut self, predicate: P) -> Vec<String> {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        let removed = features
+            .iter()
+            .map(|(feature, _)| feature)
+            .filter(|feature| !self.pinned_features.contains(*feature) && predicate(feature))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if removed.is_empty() {
+            return removed;
+        }
+
+        for feature in &removed {
+            features.remove(feature);
+        }
+        for (feature, deps) in features.iter_mut() {
+            if self.pinned_features.contains(feature.get()) {
+                continue;
+            }
+            if let Some(deps) = deps.as_array_mut() {
+                deps.retain(|dep| {
+                    dep.as_str()
+                        .map(|dep| !removed.iter().any(|feature| feature == dep))
+                        .unwrap_or(true)
+                });
+            }
+        }
+        self.generated_features
+            .retain(|feature| !removed.contains(feature));
+        for exclusion_group in self.exclusion_groups.values_mut() {
+            exclusion_group
+                .members
+                .retain(|member| !removed.contains(member));
+            exclusion_group
+                .enabled
+                .retain(|member| !removed.contains(member));
+        }
+        for members in &mut self.anonymous_exclusion_groups {
+            members.retain(|member| !removed.contains(member));
+        }
+        self.anonymous_exclusion_groups
+            .retain(|members| members.len() > 1);
+        self.parent_features
+            .retain(|parent, _| !removed.contains(parent));
+        for children in self.parent_features.values_mut() {
+            children.retain(|child| !removed.contains(child));
+        }
+        self.tiers.retain(|(tier, _)| !removed.contains(tier));
+        for (_, membership) in &mut self.tiers {
+            if let TierMembership::Features(members) = membership {
+                members.retain(|member| !removed.contains(member));
+            }
+        }
+
+        removed
+    }
+
+    /// Mark generated feature as deprecated. Feature is kept, forwarding to `replacement` if specified.\
+    /// `cargo:warning` is emitted when deprecated feature is enabled.
+    pub fn deprecate_feature(
+        &mut self,
+        name: &str,
+        replacement: Option<&str>,
+    ) -> Result<(), Error> {
+        if !self
+            .generated_features
+            .iter()
+            .any(|feature| feature == name)
+        {
+            return Err(Error::UnknownFeature(name.to_string()));
+        }
+
+        if let Some(replacement) = replacement {
+            let dependencies = self.document[FEATURES_TABLE_NAME][name]
+                .as_array_mut()
+                .unwrap();
+            if !dependencies
+                .iter()
+                .any(|dep| dep.as_str() == Some(replacement))
             {
-                specified_features.push(feature);
+                dependencies.push(replacement);
+            }
+        }
+
+        if self.feature_source.is_enabled(name) {
+            match replacement {
+                Some(replacement) => println!(
+                    "cargo:warning=feature({}) is deprecated. Please use {}",
+                    name, replacement
+                ),
+                None => println!("cargo:warning=feature({}) is deprecated", name),
             }
         }
 
-        Ok(specified_features)
+        self.deprecated_features
+            .insert(name.to_string(), replacement.map(str::to_string));
+        Ok(())
+    }
+
+    /// Deprecated features with their replacements
+    pub fn deprecated_features(&self) -> &BTreeMap<String, Option<String>> {
+        &self.deprecated_features
+    }
+
+    /// Add features to manifest. At least one of them must be enabled.
+    pub fn add_features_requiring_at_least_one<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        let generated = self.generate_features(feature_names, dependency_setter)?;
+        if generated.specified.is_empty() {
+            Err(Error::RequiredFeatureError(generated.names))
+        } else {
+            Ok(generated.specified.into_iter().collect())
+        }
+    }
+
+    /// Add mutually exclusive features to manifest. Exactly one of them must be enabled.
+    pub fn add_features_requiring_exactly_one<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<T, Error> {
+        let generated = self.generate_features(feature_names, dependency_setter)?;
+        self.anonymous_exclusion_groups
+            .push(generated.names.clone());
+        ExclusiveStrategy::Error
+            .resolve(generated.specified)?
+            .ok_or(Error::RequiredFeatureError(generated.names))
     }
 
     /// Add features to manifest. But, this features are mutually exclusive.\
@@ -284,33 +1599,583 @@ impl Manifest {
         feature_names: I,
         dependency_setter: F,
     ) -> Result<Option<T>, Error> {
-        let specified = self.add_features(feature_names, dependency_setter)?;
-        if specified.len() > 1 {
-            Err(Error::MutualExclusiveFeatureError(
-                specified.into_iter().map(|f| f.to_feature_name()).collect(),
-            ))
-        } else {
-            Ok(specified.into_iter().next())
+        self.add_mutually_exclusive_features_with_strategy(
+            feature_names,
+            dependency_setter,
+            ExclusiveStrategy::Error,
+        )
+    }
+
+    /// Same as `add_mutually_exclusive_features`, but `default` is returned when none of features is enabled
+    pub fn add_mutually_exclusive_features_with_default<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+        default: T,
+    ) -> Result<T, Error> {
+        Ok(self
+            .add_mutually_exclusive_features(feature_names, dependency_setter)?
+            .unwrap_or(default))
+    }
+
+    /// Add mutually exclusive features to manifest.\
+    /// When multiple features are enabled at the same time, one of them is chosen by `strategy`
+    pub fn add_mutually_exclusive_features_with_strategy<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        feature_names: I,
+        dependency_setter: F,
+        strategy: ExclusiveStrategy,
+    ) -> Result<Option<T>, Error> {
+        let generated = self.generate_features(feature_names, dependency_setter)?;
+        self.anonymous_exclusion_groups.push(generated.names);
+        strategy.resolve(generated.specified)
+    }
+
+    /// Declare named group of mutually exclusive features.\
+    /// Features are attached with `add_features_to_exclusion_group`, and exclusivity is checked at `write`.
+    pub fn declare_exclusion_group(&mut self, group: &str) {
+        self.exclusion_groups.entry(group.to_string()).or_default();
+    }
+
+    /// Declare named group of mutually exclusive features, whose members are named with `prefix`(e.g. `backend-`).\
+    /// Prefix is prepended by this crate, so `ToFeatureName` of members returns names without prefix.
+    pub fn declare_exclusion_group_with_prefix(&mut self, group: &str, prefix: &str) {
+        self.exclusion_groups
+            .entry(group.to_string())
+            .or_default()
+            .prefix = prefix.to_string();
+    }
+
+    /// Add features to manifest as members of declared exclusion group.\
+    /// Enabled features of this call are returned.
+    pub fn add_features_to_exclusion_group<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        group: &str,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        self.try_add_features_to_exclusion_group(group, feature_names, |feature, helper| {
+            dependency_setter(feature, helper);
+            Ok(())
+        })
+    }
+
+    /// Same as `add_features_to_exclusion_group`, with fallible dependency setter like `try_add_features`
+    pub fn try_add_features_to_exclusion_group<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>) -> Result<(), DependencyError>,
+    >(
+        &mut self,
+        group: &str,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        if !self.exclusion_groups.contains_key(group) {
+            return Err(Error::UnknownGroup(group.to_string()));
+        }
+
+        let prefix = self.exclusion_groups[group].prefix.clone();
+        let feature_names = feature_names
+            .map(|feature| Named(format!("{}{}", prefix, feature.to_feature_name()), feature));
+        let generated = self
+            .try_generate_features(Some(group), feature_names, |feature, helper| {
+                dependency_setter(&feature.1, helper)
+            })
+            .map_err(|e| e.in_group(group))?;
+        let exclusion_group = self.exclusion_groups.get_mut(group).unwrap();
+        exclusion_group.members.extend(generated.names);
+        exclusion_group.enabled.extend(
+            generated
+                .specified
+                .iter()
+                .map(ToFeatureName::to_feature_name),
+        );
+
+        Ok(FeatureSet::from_named(generated.specified))
+    }
+
+    /// Write `compile_error!` guards of all mutually exclusive features to `path`.\
+    /// Include written file from library, to protect builds not running this build script.
+    pub fn write_exclusion_guards(&self, path: &Path) -> Result<(), Error> {
+        let code = crate::codegen::exclusion_guards(self.all_exclusion_groups());
+        std::fs::write(path, code)?;
+        Ok(())
+    }
+
+    /// Write `compile_error!` guards to `OUT_DIR/file_name`
+    ///
+    /// ```ignore
+    /// include!(concat!(env!("OUT_DIR"), "/exclusion_guards.rs"));
+    /// ```
+    pub fn write_exclusion_guards_with_env(&self, file_name: &str) -> Result<PathBuf, Error> {
+        let mut path = crate::env::var_path("OUT_DIR")?;
+        path.push(file_name);
+        self.write_exclusion_guards(&path)?;
+        Ok(path)
+    }
+
+    /// Write enum named `name` mirroring members of exclusion group to `path`.\
+    /// `CURRENT` constant of the enum is set to the variant selected for current build, or default chosen by target.
+    /// Variants are named in UpperCamelCase, without prefix of the group.
+    /// Fails with `Error::VariantNameCollision` when features are mapped to the same variant(e.g. `foo-bar` and `foo_bar`).
+    pub fn write_selection_enum(&self, group: &str, name: &str, path: &Path) -> Result<(), Error> {
+        let exclusion_group = self
+            .exclusion_groups
+            .get(group)
+            .ok_or_else(|| Error::UnknownGroup(group.to_string()))?;
+        let members = exclusion_group
+            .members
+            .iter()
+            .map(|feature| {
+                let variant = crate::formatter::upper_camel_case(
+                    feature
+                        .strip_prefix(&exclusion_group.prefix)
+                        .unwrap_or(feature),
+                );
+                let variant = if variant.starts_with(|c: char| c.is_ascii_digit()) {
+                    format!("_{}", variant)
+                } else {
+                    variant
+                };
+                (variant, feature.clone())
+            })
+            .collect::<Vec<_>>();
+        for (index, (variant, feature)) in members.iter().enumerate() {
+            if let Some((_, other)) = members[..index].iter().find(|(other, _)| other == variant) {
+                return Err(Error::VariantNameCollision {
+                    variant: variant.clone(),
+                    first: other.clone(),
+                    second: feature.clone(),
+                });
+            }
+        }
+        let selected = self.selected_in_group(group)?;
+        let code = crate::codegen::selection_enum(name, &members, selected.as_deref());
+        std::fs::write(path, code)?;
+        Ok(())
+    }
+
+    /// Same as `write_selection_enum`, but enum is written to `OUT_DIR/file_name`
+    ///
+    /// ```ignore
+    /// include!(concat!(env!("OUT_DIR"), "/backend.rs"));
+    /// ```
+    pub fn write_selection_enum_with_env(
+        &self,
+        group: &str,
+        name: &str,
+        file_name: &str,
+    ) -> Result<PathBuf, Error> {
+        let mut path = crate::env::var_path("OUT_DIR")?;
+        path.push(file_name);
+        self.write_selection_enum(group, name, &path)?;
+        Ok(path)
+    }
+
+    /// Add tier meta-feature(e.g. `full`) enabling its members.\
+    /// Members are computed when writing, so tiers follow changes of generated groups.
+    /// Fails with `Error::InvalidFeatureName` when `name` is not valid feature name.
+    pub fn add_tier(&mut self, name: &str, membership: TierMembership) -> Result<(), Error> {
+        let name = crate::FeatureName::validated(name.to_string())?;
+        self.tiers.push((name.into_string(), membership));
+        Ok(())
+    }
+
+    pub(crate) fn apply_tiers(&mut self) {
+        let tiers = self.tier_entries();
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        for (name, members) in tiers {
+            insert_generated_feature(features, &name, members);
+        }
+    }
+
+    /// Tier meta-features with their members
+    pub(crate) fn tier_entries(&self) -> Vec<(String, Vec<String>)> {
+        let exclusive = self
+            .all_exclusion_groups()
+            .flatten()
+            .cloned()
+            .collect::<HashSet<_>>();
+        self.tiers
+            .iter()
+            .map(|(name, membership)| {
+                let members = match membership {
+                    TierMembership::NonExclusive => self
+                        .generated_features
+                        .iter()
+                        .filter(|feature| !exclusive.contains(*feature))
+                        .cloned()
+                        .collect(),
+                    TierMembership::AllGenerated => self.generated_features.clone(),
+                    TierMembership::Features(features) => features.clone(),
+                };
+                (name.clone(), members)
+            })
+            .collect()
+    }
+
+    /// Declare parent feature, which enables all of its children.\
+    /// Parent can be nested under already declared parent feature.
+    /// Fails with `Error::ParentFeatureCycle` when `parent` is `name` itself or its descendant,
+    /// and with `Error::InvalidFeatureName` when `name` is not valid feature name.
+    pub fn declare_parent_feature(
+        &mut self,
+        name: &str,
+        parent: Option<&str>,
+    ) -> Result<(), Error> {
+        let name = crate::FeatureName::validated(name.to_string()).map_err(|e| match parent {
+            Some(parent) => e.in_group(parent),
+            None => e,
+        })?;
+        let name = name.as_str();
+        if let Some(parent) = parent {
+            if !self.parent_features.contains_key(parent) {
+                return Err(Error::UnknownGroup(parent.to_string()));
+            }
+            if self.is_descendant(parent, name) {
+                return Err(Error::ParentFeatureCycle {
+                    parent: parent.to_string(),
+                    child: name.to_string(),
+                });
+            }
+            let children = self.parent_features.get_mut(parent).unwrap();
+            if !children.iter().any(|child| child == name) {
+                children.push(name.to_string());
+            }
         }
+        self.parent_features.entry(name.to_string()).or_default();
+        Ok(())
+    }
+
+    /// Whether `feature` is `ancestor` itself or reachable through its children
+    fn is_descendant(&self, feature: &str, ancestor: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut queue = vec![ancestor];
+        while let Some(current) = queue.pop() {
+            if current == feature {
+                return true;
+            }
+            if visited.insert(current) {
+                queue.extend(
+                    self.parent_features
+                        .get(current)
+                        .into_iter()
+                        .flatten()
+                        .map(String::as_str),
+                );
+            }
+        }
+        false
+    }
+
+    /// Add features to manifest as children of declared parent feature.
+    pub fn add_features_under_parent<
+        T: ToFeatureName,
+        I: Iterator<Item = T>,
+        F: Fn(&'_ T, &mut DependencyHelper<'_>),
+    >(
+        &mut self,
+        parent: &str,
+        feature_names: I,
+        dependency_setter: F,
+    ) -> Result<FeatureSet<T>, Error> {
+        if !self.parent_features.contains_key(parent) {
+            return Err(Error::UnknownGroup(parent.to_string()));
+        }
+
+        let generated =
+            self.generate_features_in_group(parent, feature_names, dependency_setter)?;
+        let children = self.parent_features.get_mut(parent).unwrap();
+        for name in generated.names {
+            if !children.contains(&name) {
+                children.push(name);
+            }
+        }
+        Ok(generated.specified.into_iter().collect())
+    }
+
+    pub(crate) fn apply_parent_features(&mut self) {
+        let features = self.document[FEATURES_TABLE_NAME].as_table_mut().unwrap();
+        for (name, children) in &self.parent_features {
+            insert_generated_feature(features, name, children.clone());
+        }
+    }
+
+    pub(crate) fn all_exclusion_groups(&self) -> impl Iterator<Item = &[String]> {
+        self.exclusion_groups
+            .values()
+            .map(|group| group.members.as_slice())
+            .chain(self.anonymous_exclusion_groups.iter().map(Vec::as_slice))
+    }
+
+    /// Iterate valid combinations of generated features.\
+    /// Mutually exclusive features and dependencies between generated features are respected.
+    pub fn feature_combinations(&self) -> FeatureCombinations {
+        let features = self.document.as_table()[FEATURES_TABLE_NAME]
+            .as_table()
+            .unwrap();
+        let implications = self
+            .generated_features
+            .iter()
+            .map(|feature| {
+                let implied = features
+                    .get(feature)
+                    .and_then(Item::as_array)
+                    .map(|deps| {
+                        deps.iter()
+                            .filter_map(Value::as_str)
+                            .filter(|dep| self.generated_features.iter().any(|f| f == dep))
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (feature.clone(), implied)
+            })
+            .collect();
+
+        FeatureCombinations::new(
+            &self.generated_features,
+            self.all_exclusion_groups()
+                .map(<[String]>::to_vec)
+                .collect(),
+            implications,
+        )
+    }
+
+    pub(crate) fn check_exclusion_groups(&self) -> Result<(), Error> {
+        for group in self.exclusion_groups.values() {
+            if group.enabled.len() > 1 {
+                return Err(Error::MutualExclusiveFeatureError(group.enabled.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn content_without_features(document: &toml_edit::DocumentMut) -> String {
+        let mut document = document.clone();
+        document.remove(FEATURES_TABLE_NAME);
+        document.to_string()
     }
 
     fn check_is_changed(&self) -> Result<bool, Error> {
-        let current_features = Self::collect_features(&self.document)?;
+        if self.template_path.is_some() {
+            let written = std::fs::read_to_string(&self.path).ok();
+            return Ok(written.map(|written| LineEnding::Lf.apply(&written))
+                != Some(LineEnding::Lf.apply(&self.document.to_string())));
+        }
+        let current_features = Self::collect_features(&self.path, self.document.as_table(), None)?;
 
-        Ok(current_features != self.original_features)
+        Ok(current_features != self.original_features
+            || Self::content_without_features(&self.document) != self.original_content)
     }
 
     /// When manifest is changed, write back to the manifest file & return `Error::ManifestChanged`
+    ///
+    /// Read-only manifest is never written. Warning is emitted instead when it is outdated.\
+    /// Manifest is not compared nor written when generation is skipped by `GenerationPolicy`.\
+    /// Fails when multiple features of an exclusion group are enabled, or selection of `FeatureSource` is invalid.
     pub fn write(self) -> Result<bool, Error> {
+        self.write_with_options(&Default::default())
+    }
+
+    /// Same as `write`, with specified options
+    pub fn write_with_options(self, options: &WriteOptions) -> Result<bool, Error> {
+        Ok(self.write_with_report(options)?.written)
+    }
+
+    /// Same as `write_with_options`, but report of generation is returned
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %self.path.display()))
+    )]
+    pub fn write_with_report(mut self, options: &WriteOptions) -> Result<GenerationReport, Error> {
+        let report = self.write_document(options)?;
+        if report.written && self.prevent_build_when_changed {
+            Err(Error::ManifestChanged)
+        } else {
+            Ok(report)
+        }
+    }
+
+    /// Validate selection and apply generated entries to document, without writing
+    pub(crate) fn apply_generation(&mut self) -> Result<(), Error> {
+        self.feature_source.validate(
+            &self
+                .generated_features
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+        )?;
+        self.check_exclusion_groups()?;
+        self.check_rust_versions()?;
+        self.apply_tiers();
+        self.apply_parent_features();
+        if self.maintain_check_cfg {
+            self.update_check_cfg();
+        }
+        self.apply_patches()?;
+        if self.record_state {
+            self.store_state();
+        }
+        Ok(())
+    }
+
+    /// Apply generation and write manifest when changed. `written` of report is set when written.
+    pub(crate) fn write_document(
+        &mut self,
+        options: &WriteOptions,
+    ) -> Result<GenerationReport, Error> {
+        self.apply_generation()?;
+        self.write_generated(options)
+    }
+
+    /// Write manifest already applied by `apply_generation` when changed
+    pub(crate) fn write_generated(
+        &mut self,
+        options: &WriteOptions,
+    ) -> Result<GenerationReport, Error> {
+        let mut report = self.generation_report()?;
+        report.exports = self.emit_exports()?;
+        report.lints = self.lints(&options.lints);
+        for lint in &report.lints {
+            println!("cargo:warning={}", lint);
+        }
+        if options.warn_unused_features {
+            report.unused_features = self.unused_generated_features()?;
+            for feature in &report.unused_features {
+                println!(
+                    "cargo:warning=feature({}) is never referenced in source",
+                    feature
+                );
+            }
+        }
+        if options.unknown_features != UnknownFeaturePolicy::Ignore {
+            report.unknown_usages = self.unknown_feature_usages()?;
+            if !report.unknown_usages.is_empty()
+                && options.unknown_features == UnknownFeaturePolicy::Error
+            {
+                return Err(Error::UnknownFeatureUsage(report.unknown_usages));
+            }
+            for usage in &report.unknown_usages {
+                println!(
+                    "cargo:warning={}:{}: feature({}) is not declared",
+                    usage.path.display(),
+                    usage.location.line,
+                    usage.feature
+                );
+            }
+        }
+        if !self.should_generate() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(policy = ?self.generation_policy, "generation skipped");
+            return Ok(report);
+        }
+
         if self.check_is_changed()? {
-            std::fs::write(&self.path, self.document.to_string())?;
-            if self.prevent_build_when_changed {
-                Err(Error::ManifestChanged)
-            } else {
-                Ok(true)
+            if self.read_only {
+                println!(
+                    "cargo:warning=generated features of read-only manifest({}) are outdated",
+                    self.path.display()
+                );
+                #[cfg(feature = "tracing")]
+                tracing::warn!("read-only manifest is outdated");
+                return Ok(report);
+            }
+            let line_ending = options.line_ending.unwrap_or(self.line_ending);
+            std::fs::write(&self.path, line_ending.apply(&self.document.to_string()))
+                .map_err(|e| Error::from(e).at_manifest(&self.path))?;
+            if options.warn_summary {
+                for line in report.summary() {
+                    println!("cargo:warning={}", line);
+                }
             }
+            #[cfg(feature = "tracing")]
+            tracing::debug!("manifest written");
+            report.written = true;
+            self.hooks.run(&report);
+            Ok(report)
         } else {
-            Ok(false)
+            Ok(report)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn merge_policy() {
+        let existing = names(&["a", "manual"]);
+        let generated = names(&["a", "b"]);
+        assert_eq!(
+            MergePolicy::Replace
+                .merge("f", existing.clone(), generated.clone())
+                .unwrap(),
+            names(&["a", "b"])
+        );
+        assert_eq!(
+            MergePolicy::MergeUnion
+                .merge("f", existing.clone(), generated.clone())
+                .unwrap(),
+            names(&["a", "b", "manual"])
+        );
+        match MergePolicy::FailOnManualEdit.merge("f", existing, generated.clone()) {
+            Err(Error::ManualEditConflict {
+                feature,
+                dependencies,
+            }) => {
+                assert_eq!(feature, "f");
+                assert_eq!(dependencies, ["manual"]);
+            }
+            result => panic!("unexpected result - {:?}", result),
+        }
+        assert!(MergePolicy::FailOnManualEdit
+            .merge("f", names(&["b"]), generated)
+            .is_ok());
+    }
+
+    #[test]
+    fn invalid_generated_names() {
+        let mut manifest = Manifest::from_source(
+            PathBuf::from("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+            false,
+        )
+        .unwrap();
+        assert!(matches!(
+            manifest.add_tier("full tier", TierMembership::AllGenerated),
+            Err(Error::InvalidFeatureName { group: None, .. })
+        ));
+        assert!(matches!(
+            manifest.add_feature_aliases([("dep:old", "new")].into_iter()),
+            Err(Error::InvalidFeatureName { .. })
+        ));
+        manifest.declare_parent_feature("graphics", None).unwrap();
+        match manifest.declare_parent_feature("gl/es", Some("graphics")) {
+            Err(Error::InvalidFeatureName { name, group, .. }) => {
+                assert_eq!(name, "gl/es");
+                assert_eq!(group.as_deref(), Some("graphics"));
+            }
+            result => panic!("unexpected result - {:?}", result),
         }
     }
 }
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use crate::{transaction::Snapshot, Error, Manifest, WriteOptions};
+
+/// Several manifests(current crate and crates receiving propagated features), written all together.\
+/// When writing any of them fails, already written manifests and lockfiles are restored.
+#[derive(Default)]
+pub struct ManifestSet {
+    manifests: Vec<Manifest>,
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+impl ManifestSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add manifest to set. Manifest of same file is replaced.
+    pub fn insert(&mut self, manifest: Manifest) {
+        if let Some(index) = self.position(&manifest.path) {
+            self.manifests[index] = manifest;
+        } else {
+            self.manifests.push(manifest);
+        }
+    }
+
+    /// Load manifest at `path` and add it to set
+    pub fn load(&mut self, path: PathBuf) -> Result<&mut Manifest, Error> {
+        self.insert(Manifest::new(path.clone(), false)?);
+        Ok(self.get_mut(&path).unwrap())
+    }
+
+    fn position(&self, path: &Path) -> Option<usize> {
+        self.manifests
+            .iter()
+            .position(|manifest| same_file(&manifest.path, path))
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Manifest> {
+        self.position(path).map(|index| &self.manifests[index])
+    }
+
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Manifest> {
+        self.position(path).map(|index| &mut self.manifests[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Manifest> {
+        self.manifests.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Manifest> {
+        self.manifests.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.manifests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.manifests.is_empty()
+    }
+
+    /// Check features referring to path dependencies in set(`crate/feature`, `crate?/feature`) exist in their manifests
+    pub fn validate(&self) -> Result<(), Error> {
+        for manifest in &self.manifests {
+            for (feature, deps) in manifest.features() {
+                for dep in deps {
+                    let Some((crate_name, dependency_feature)) = dep.split_once('/') else {
+                        continue;
+                    };
+                    let crate_name = crate_name.trim_end_matches('?');
+                    let Some(path) = manifest
+                        .dependency_spec(crate_name)?
+                        .and_then(|spec| spec.path)
+                    else {
+                        continue;
+                    };
+                    let Some(target) = self.get(&path.join("Cargo.toml")) else {
+                        continue;
+                    };
+                    if !target.has_feature(dependency_feature) {
+                        return Err(Error::InconsistentFeature {
+                            path: manifest.path.clone(),
+                            feature: feature.to_string(),
+                            dependency: dep.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate and write every manifest
+    pub fn write(self) -> Result<bool, Error> {
+        self.write_with_options(&Default::default())
+    }
+
+    /// Same as `write`, with specified options.\
+    /// Generation of every manifest is applied before validation, so tier and parent features can be referred.\
+    /// `Error::ManifestChanged` is returned after every manifest is written, if any manifest requests it.
+    pub fn write_with_options(mut self, options: &WriteOptions) -> Result<bool, Error> {
+        for manifest in &mut self.manifests {
+            manifest.apply_generation()?;
+        }
+        self.validate()?;
+
+        let snapshot = Snapshot::capture(
+            self.manifests
+                .iter()
+                .flat_map(|manifest| [manifest.path.clone(), manifest.lock_path()]),
+        )?;
+
+        let mut written = false;
+        let mut changed = false;
+        for mut manifest in self.manifests {
+            match manifest.write_generated(options) {
+                Ok(report) => {
+                    written |= report.written;
+                    changed |= report.written && manifest.prevent_build_when_changed;
+                }
+                Err(e) => {
+                    snapshot.restore()?;
+                    return Err(e);
+                }
+            }
+        }
+
+        if changed {
+            Err(Error::ManifestChanged)
+        } else {
+            Ok(written)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dependency, TierMembership};
+
+    fn manifest_set() -> ManifestSet {
+        let dir = std::env::temp_dir().join("manifest-feature-gen-set-test");
+        let app = Manifest::from_source(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nlib = { path = \"lib\" }\n",
+            false,
+        )
+        .unwrap();
+        let mut lib = Manifest::from_source(
+            dir.join("lib").join("Cargo.toml"),
+            "[package]\nname = \"lib\"\nversion = \"0.1.0\"\n",
+            false,
+        )
+        .unwrap();
+        lib.add_features(["std"].into_iter(), |_, _| {}).unwrap();
+        lib.add_tier("full", TierMembership::AllGenerated).unwrap();
+
+        let mut set = ManifestSet::new();
+        set.insert(app);
+        set.insert(lib);
+        set
+    }
+
+    fn propagate(set: &mut ManifestSet, feature: &'static str) {
+        let path = std::env::temp_dir()
+            .join("manifest-feature-gen-set-test")
+            .join("Cargo.toml");
+        set.get_mut(&path)
+            .unwrap()
+            .add_features([feature].into_iter(), |feature, deps| {
+                deps.add(Dependency::crate_feature("lib", *feature))
+                    .unwrap();
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn propagation_to_declared_feature() {
+        let mut set = manifest_set();
+        propagate(&mut set, "std");
+        set.validate().unwrap();
+    }
+
+    #[test]
+    fn propagation_to_undeclared_feature() {
+        let mut set = manifest_set();
+        propagate(&mut set, "alloc");
+        match set.validate() {
+            Err(Error::InconsistentFeature {
+                feature,
+                dependency,
+                ..
+            }) => {
+                assert_eq!(feature, "alloc");
+                assert_eq!(dependency, "lib/alloc");
+            }
+            result => panic!("unexpected result - {:?}", result),
+        }
+    }
+
+    #[test]
+    fn propagation_to_tier_after_generation() {
+        let mut set = manifest_set();
+        propagate(&mut set, "full");
+        assert!(set.validate().is_err());
+        for manifest in set.iter_mut() {
+            manifest.apply_generation().unwrap();
+        }
+        set.validate().unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    path::PathBuf,
+    process::Command,
+    sync::{Mutex, PoisonError},
+};
+
+const DEFAULT_JOBS: usize = 2;
+
+/// Checks every feature combination by running `cargo check` on it.
+///
+/// Combinations are usually provided by `Manifest::feature_combinations`.
+/// Each worker uses its own target directory, so checks can run in parallel.
+/// Every target directory builds dependencies on its own, so increase `jobs` only with enough disk and cores.
+#[derive(Debug, Clone)]
+pub struct MatrixCheck {
+    /// Manifest of the crate to check
+    pub manifest_path: PathBuf,
+    /// Number of checks running at the same time, which is also number of target directories
+    pub jobs: usize,
+    /// Base of per-worker target directories. `target/feature-matrix` next to manifest is used when `None`.
+    pub target_dir: Option<PathBuf>,
+    /// Additional arguments passed to `cargo check`
+    pub cargo_args: Vec<String>,
+}
+
+/// Result of checking single feature combination
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatrixResult {
+    /// Features enabled for this check, along with `--no-default-features`
+    pub features: Vec<String>,
+    /// Whether `cargo check` succeeded. `false` also when cargo cannot be run.
+    pub success: bool,
+    /// Captured stderr of cargo
+    pub output: String,
+}
+
+/// Results of `MatrixCheck::run`, in the order of given combinations
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatrixSummary {
+    pub results: Vec<MatrixResult>,
+}
+
+impl MatrixCheck {
+    /// Check crate of specified manifest, running up to 2 checks at the same time
+    pub fn new(manifest_path: PathBuf) -> Self {
+        Self {
+            manifest_path,
+            jobs: std::thread::available_parallelism()
+                .map(|jobs| jobs.get().min(DEFAULT_JOBS))
+                .unwrap_or(1),
+            target_dir: None,
+            cargo_args: Vec::new(),
+        }
+    }
+
+    fn target_dir(&self) -> PathBuf {
+        self.target_dir.clone().unwrap_or_else(|| {
+            let mut dir = self
+                .manifest_path
+                .parent()
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_default();
+            dir.push("target");
+            dir.push("feature-matrix");
+            dir
+        })
+    }
+
+    fn check(&self, worker: usize, features: Vec<String>) -> MatrixResult {
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        let output = Command::new(cargo)
+            .arg("check")
+            .arg("--manifest-path")
+            .arg(&self.manifest_path)
+            .arg("--no-default-features")
+            .arg("--features")
+            .arg(features.join(","))
+            .args(&self.cargo_args)
+            .env(
+                "CARGO_TARGET_DIR",
+                self.target_dir().join(worker.to_string()),
+            )
+            .output();
+
+        match output {
+            Ok(output) => MatrixResult {
+                features,
+                success: output.status.success(),
+                output: String::from_utf8_lossy(&output.stderr).into_owned(),
+            },
+            Err(e) => MatrixResult {
+                features,
+                success: false,
+                output: e.to_string(),
+            },
+        }
+    }
+
+    /// Run `cargo check` for each combination
+    pub fn run<I: Iterator<Item = Vec<String>> + Send>(&self, combinations: I) -> MatrixSummary {
+        let combinations = Mutex::new(combinations.enumerate());
+        let results = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for worker in 0..self.jobs.max(1) {
+                let (combinations, results) = (&combinations, &results);
+                scope.spawn(move || loop {
+                    let next = combinations
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .next();
+                    let Some((index, features)) = next else {
+                        break;
+                    };
+                    let result = self.check(worker, features);
+                    results
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push((index, result));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap_or_else(PoisonError::into_inner);
+        results.sort_by_key(|(index, _)| *index);
+        MatrixSummary {
+            results: results.into_iter().map(|(_, result)| result).collect(),
+        }
+    }
+}
+
+impl MatrixSummary {
+    pub fn passed(&self) -> impl Iterator<Item = &MatrixResult> {
+        self.results.iter().filter(|result| result.success)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &MatrixResult> {
+        self.results.iter().filter(|result| !result.success)
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+impl std::fmt::Display for MatrixSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} passed, {} failed out of {} combinations",
+            self.passed().count(),
+            self.failed().count(),
+            self.results.len()
+        )?;
+        for result in self.failed() {
+            writeln!(f, "failed: [{}]", result.features.join(","))?;
+        }
+        Ok(())
+    }
+}
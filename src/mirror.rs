@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashSet};
+
+use toml_edit::ImDocument;
+
+use crate::{
+    manifest::{Named, FEATURES_TABLE_NAME},
+    Error, Manifest,
+};
+
+impl Manifest {
+    /// Features exposed by dependency. Path dependencies are read directly,
+    /// and others are resolved with `cargo metadata` when `metadata` feature is enabled.
+    pub(crate) fn dependency_features(
+        &self,
+        crate_name: &str,
+    ) -> Result<BTreeMap<String, HashSet<String>>, Error> {
+        let spec = self
+            .dependency_spec(crate_name)?
+            .ok_or_else(|| Error::UnknownDependency(crate_name.to_string()))?;
+
+        if let Some(path) = &spec.path {
+            let manifest_path = path.join("Cargo.toml");
+            let source = std::fs::read_to_string(&manifest_path)
+                .map_err(|e| Error::from(e).at_manifest(&manifest_path))?;
+            let document = ImDocument::parse(source.as_str())
+                .map_err(|e| Error::from(e).at_manifest(&manifest_path))?;
+            return Ok(
+                Self::collect_features(&manifest_path, document.as_table(), Some(&source))?
+                    .into_iter()
+                    .collect(),
+            );
+        }
+
+        let package_name = spec.package.as_deref().unwrap_or(crate_name);
+        self.resolve_dependency_features(crate_name, package_name)
+    }
+
+    #[cfg(feature = "metadata")]
+    fn resolve_dependency_features(
+        &self,
+        crate_name: &str,
+        package_name: &str,
+    ) -> Result<BTreeMap<String, HashSet<String>>, Error> {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&self.path)
+            .exec()
+            .map_err(|_| Error::UnresolvedDependency(crate_name.to_string()))?;
+        let resolve = metadata
+            .resolve
+            .as_ref()
+            .ok_or_else(|| Error::UnresolvedDependency(crate_name.to_string()))?;
+        let root = resolve
+            .root
+            .as_ref()
+            .and_then(|root| resolve.nodes.iter().find(|node| &node.id == root))
+            .ok_or_else(|| Error::UnresolvedDependency(crate_name.to_string()))?;
+        let package = root
+            .dependencies
+            .iter()
+            .filter_map(|id| metadata.packages.iter().find(|package| &package.id == id))
+            .find(|package| package.name.as_str() == package_name)
+            .ok_or_else(|| Error::UnresolvedDependency(crate_name.to_string()))?;
+
+        Ok(package
+            .features
+            .iter()
+            .map(|(feature, deps)| (feature.clone(), deps.iter().cloned().collect()))
+            .collect())
+    }
+
+    #[cfg(not(feature = "metadata"))]
+    fn resolve_dependency_features(
+        &self,
+        crate_name: &str,
+        _package_name: &str,
+    ) -> Result<BTreeMap<String, HashSet<String>>, Error> {
+        Err(Error::UnresolvedDependency(crate_name.to_string()))
+    }
+
+    /// Generate same-named feature forwarding to `crate_name/<feature>` for each feature of dependency.\
+    /// Only features passing `filter` are mirrored. `default` is never mirrored.
+    ///
+    /// Enabled mirrored features are returned.
+    pub fn mirror_dependency_features<P: Fn(&str) -> bool>(
+        &mut self,
+        crate_name: &str,
+        filter: P,
+    ) -> Result<Vec<String>, Error> {
+        let features = self
+            .dependency_features(crate_name)?
+            .into_keys()
+            .filter(|feature| feature != "default" && filter(feature))
+            .map(|feature| Named(feature, ()))
+            .collect::<Vec<_>>();
+
+        let specified = self.add_features(features.into_iter(), |feature, helper| {
+            helper
+                .add_crate_feature_dependency(crate_name, &feature.0, false)
+                .expect("single dependency never conflicts");
+        })?;
+
+        Ok(specified.into_iter().map(|feature| feature.0).collect())
+    }
+}
+
+/// Rules of `Manifest::forward_all_features`
+#[derive(Debug, Clone, Default)]
+pub struct ForwardRules {
+    /// Upstream features not forwarded
+    pub exclude: Vec<String>,
+    /// Local names of upstream features. Upstream name is used when not listed.
+    pub rename: BTreeMap<String, String>,
+    /// Forward `default` feature. Dependency should be declared with `default-features = false`.
+    pub forward_default: bool,
+}
+
+impl Manifest {
+    /// Regenerate whole `[features]` table as forwarding layer over features of `crate_name`.\
+    /// Every existing feature, including hand-written ones, is removed except pinned ones.
+    ///
+    /// Enabled forwarding features are returned with local names.
+    pub fn forward_all_features(
+        &mut self,
+        crate_name: &str,
+        rules: &ForwardRules,
+    ) -> Result<Vec<String>, Error> {
+        let upstream = self.dependency_features(crate_name)?;
+
+        let pinned = &self.pinned_features;
+        self.document[FEATURES_TABLE_NAME]
+            .as_table_mut()
+            .unwrap()
+            .retain(|feature, _| pinned.contains(feature));
+
+        let features = upstream
+            .into_keys()
+            .filter(|feature| {
+                (rules.forward_default || feature != "default") && !rules.exclude.contains(feature)
+            })
+            .map(|feature| {
+                let local = rules.rename.get(&feature).unwrap_or(&feature).clone();
+                Named(local, feature)
+            })
+            .collect::<Vec<_>>();
+
+        let specified = self.add_features(features.into_iter(), |feature, helper| {
+            helper
+                .add_crate_feature_dependency(crate_name, &feature.1, false)
+                .expect("single dependency never conflicts");
+        })?;
+
+        Ok(specified.into_iter().map(|feature| feature.0).collect())
+    }
+}
@@ -0,0 +1,92 @@
+use toml_edit::Item;
+
+use crate::{Error, ItemKind, Manifest, RustcVersion, WorkspaceManifest};
+
+const RUST_VERSION_KEY: &str = "rust-version";
+
+impl Manifest {
+    /// Record minimum rust-version(e.g. `1.70`) required by `features`.\
+    /// While writing, generation fails with `Error::IncompatibleRustVersion`
+    /// when generated ones require newer version than `package.rust-version`.
+    pub fn require_rust_version<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        features: I,
+        rust_version: &str,
+    ) -> Result<(), Error> {
+        let version = RustcVersion::parse_rust_version(rust_version)
+            .ok_or_else(|| Error::RustcVersionError(rust_version.to_string()))?;
+        for feature in features {
+            self.rust_versions.insert(feature.to_string(), version);
+        }
+        Ok(())
+    }
+
+    /// Generated features with their minimum rust-version
+    pub(crate) fn required_rust_versions(&self) -> impl Iterator<Item = (&str, RustcVersion)> {
+        self.rust_versions
+            .iter()
+            .filter(|(feature, _)| self.generated_features.contains(feature))
+            .map(|(feature, version)| (feature.as_str(), *version))
+    }
+
+    /// `package.rust-version`, resolved against workspace when inherited
+    pub fn rust_version(&self) -> Result<Option<RustcVersion>, Error> {
+        let Some(item) = self
+            .document
+            .get("package")
+            .and_then(|package| package.get(RUST_VERSION_KEY))
+        else {
+            return Ok(None);
+        };
+
+        let inherited;
+        let item = if item.get("workspace").and_then(Item::as_bool) == Some(true) {
+            let root = self
+                .workspace_root_path()?
+                .ok_or_else(|| Error::NotInWorkspace(self.path.clone()))?;
+            inherited = WorkspaceManifest::open(root)?;
+            match inherited.package_field(RUST_VERSION_KEY) {
+                Some(item) => item,
+                None => return Ok(None),
+            }
+        } else {
+            item
+        };
+
+        let rust_version = item.as_str().ok_or_else(|| {
+            Error::malformed(
+                &self.path,
+                &["package", RUST_VERSION_KEY],
+                ItemKind::String,
+                ItemKind::of_item(item),
+                None,
+            )
+        })?;
+        RustcVersion::parse_rust_version(rust_version)
+            .map(Some)
+            .ok_or_else(|| Error::RustcVersionError(rust_version.to_string()))
+    }
+
+    /// Check generated features against `package.rust-version`. Nothing is checked when it is not declared.
+    pub(crate) fn check_rust_versions(&self) -> Result<(), Error> {
+        let Some(rust_version) = self.rust_version()? else {
+            return Ok(());
+        };
+        let features = self
+            .required_rust_versions()
+            .filter(|(_, required)| {
+                (required.major, required.minor, required.patch)
+                    > (rust_version.major, rust_version.minor, rust_version.patch)
+            })
+            .map(|(feature, required)| format!("{}({})", feature, required))
+            .collect::<Vec<_>>();
+        if features.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::IncompatibleRustVersion {
+                rust_version: rust_version.to_string(),
+                features,
+            })
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{Error, ToFeatureName};
+
+/// Reason of invalid feature name
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FeatureNameError {
+    #[error("Feature name is empty")]
+    Empty,
+    #[error("Feature name cannot start with `dep:`")]
+    DepPrefix,
+    #[error("Feature name cannot start with {0:?}")]
+    InvalidStart(char),
+    #[error("Feature name cannot contain {0:?}")]
+    InvalidCharacter(char),
+}
+
+/// Validate feature name against grammar accepted by cargo.\
+/// Name should start with ASCII alphanumeric or `_`, followed by ASCII alphanumerics, `_`, `-`, `+` or `.`.
+pub fn validate_feature_name(name: &str) -> Result<(), FeatureNameError> {
+    if name.starts_with("dep:") {
+        return Err(FeatureNameError::DepPrefix);
+    }
+
+    let mut chars = name.chars();
+    match chars.next() {
+        None => return Err(FeatureNameError::Empty),
+        Some(c) if !(c.is_ascii_alphanumeric() || c == '_') => {
+            return Err(FeatureNameError::InvalidStart(c))
+        }
+        _ => {}
+    }
+
+    match chars.find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.'))) {
+        Some(c) => Err(FeatureNameError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+/// Feature name validated against grammar accepted by cargo
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct FeatureName(String);
+
+impl FeatureName {
+    /// Validate `name` as feature name
+    pub fn new<S: Into<String>>(name: S) -> Result<Self, FeatureNameError> {
+        let name = name.into();
+        validate_feature_name(&name)?;
+        Ok(Self(name))
+    }
+
+    /// Make valid feature name from arbitrary string.\
+    /// Leading characters not allowed at start are trimmed, and other disallowed characters are replaced with `-`.
+    pub fn sanitize(name: &str) -> Result<Self, FeatureNameError> {
+        let sanitized = name
+            .trim_start_matches(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.') {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect::<String>();
+        Self::new(sanitized)
+    }
+
+    /// Same as `new`, failing with `Error::InvalidFeatureName` which keeps `name`
+    pub(crate) fn validated(name: String) -> Result<Self, Error> {
+        validate_feature_name(&name).map_err(|source| Error::InvalidFeatureName {
+            name: name.clone(),
+            group: None,
+            source,
+        })?;
+        Ok(Self(name))
+    }
+
+    /// Name already validated by this crate, or read from `[features]` of manifest
+    pub(crate) fn new_unchecked(name: String) -> Self {
+        Self(name)
+    }
+
+    /// Name as string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwrap into owned string
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::fmt::Display for FeatureName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for FeatureName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::borrow::Borrow<str> for FeatureName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for FeatureName {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for FeatureName {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl TryFrom<String> for FeatureName {
+    type Error = FeatureNameError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl TryFrom<&str> for FeatureName {
+    type Error = FeatureNameError;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl From<FeatureName> for String {
+    fn from(name: FeatureName) -> Self {
+        name.0
+    }
+}
+
+impl ToFeatureName for FeatureName {
+    fn to_feature_name(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// Name returned by formatter, validated into `FeatureName`
+pub trait IntoFeatureName {
+    fn into_feature_name(self) -> Result<FeatureName, Error>;
+}
+
+impl IntoFeatureName for FeatureName {
+    fn into_feature_name(self) -> Result<FeatureName, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoFeatureName for Result<FeatureName, Error> {
+    fn into_feature_name(self) -> Result<FeatureName, Error> {
+        self
+    }
+}
+
+impl IntoFeatureName for String {
+    fn into_feature_name(self) -> Result<FeatureName, Error> {
+        FeatureName::validated(self)
+    }
+}
+
+impl IntoFeatureName for &str {
+    fn into_feature_name(self) -> Result<FeatureName, Error> {
+        FeatureName::validated(self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        assert_eq!(validate_feature_name("serde_json-1.0+std"), Ok(()));
+        assert_eq!(validate_feature_name("_private"), Ok(()));
+        assert_eq!(validate_feature_name(""), Err(FeatureNameError::Empty));
+        assert_eq!(
+            validate_feature_name("dep:serde"),
+            Err(FeatureNameError::DepPrefix)
+        );
+        assert_eq!(
+            validate_feature_name("-std"),
+            Err(FeatureNameError::InvalidStart('-'))
+        );
+        assert_eq!(
+            validate_feature_name("a/b"),
+            Err(FeatureNameError::InvalidCharacter('/'))
+        );
+    }
+
+    #[test]
+    fn sanitize() {
+        assert_eq!(FeatureName::sanitize(".hidden dir").unwrap(), "hidden-dir");
+        assert_eq!(FeatureName::sanitize("en_US.utf8").unwrap(), "en_US.utf8");
+        assert_eq!(
+            FeatureName::sanitize("--").unwrap_err(),
+            FeatureNameError::Empty
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use crate::{Manifest, NativeProbeReport, ToFeatureName};
+
+/// Availability of native libraries queried with pkg-config.
+///
+/// Results are cached, so the same library can be queried from multiple dependency setters.\
+/// `cargo:rerun-if-env-changed` is emitted for environment variables read by pkg-config.
+#[derive(Debug, Default)]
+pub struct NativeProbe {
+    results: RefCell<BTreeMap<String, Result<String, String>>>,
+    skipped: RefCell<BTreeMap<String, Vec<String>>>,
+}
+
+impl NativeProbe {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Query whether `library` is available, optionally at least of `min_version`
+    pub fn probe(&self, library: &str, min_version: Option<&str>) -> bool {
+        let key = match min_version {
+            Some(version) => format!("{} >= {}", library, version),
+            None => library.to_string(),
+        };
+        self.results
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| {
+                let mut config = pkg_config::Config::new();
+                config.cargo_metadata(false).env_metadata(true);
+                if let Some(version) = min_version {
+                    config.atleast_version(version);
+                }
+                config
+                    .probe(library)
+                    .map(|library| library.version)
+                    .map_err(|e| e.to_string())
+            })
+            .is_ok()
+    }
+
+    /// Keep features whose native `requirements` are all available.\
+    /// `cargo:warning` is emitted for each skipped feature.
+    pub fn available<T: ToFeatureName, I: Iterator<Item = T>, R: Fn(&T) -> Vec<&str>>(
+        &self,
+        features: I,
+        requirements: R,
+    ) -> Vec<T> {
+        features
+            .filter(|feature| {
+                let missing = requirements(feature)
+                    .into_iter()
+                    .filter(|library| !self.probe(library, None))
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    println!(
+                        "cargo:warning=feature({}) is skipped. missing native libraries - {}",
+                        feature.to_feature_name(),
+                        missing.join(", ")
+                    );
+                    self.skipped.borrow_mut().insert(
+                        feature.to_feature_name(),
+                        missing.iter().map(|library| library.to_string()).collect(),
+                    );
+                }
+                missing.is_empty()
+            })
+            .collect()
+    }
+
+    /// Queried libraries and versions found
+    pub fn found(&self) -> Vec<(String, String)> {
+        self.results
+            .borrow()
+            .iter()
+            .filter_map(|(library, result)| {
+                result
+                    .as_ref()
+                    .ok()
+                    .map(|version| (library.clone(), version.clone()))
+            })
+            .collect()
+    }
+
+    /// Queried libraries not found, with diagnostics of pkg-config
+    pub fn missing(&self) -> Vec<(String, String)> {
+        self.results
+            .borrow()
+            .iter()
+            .filter_map(|(library, result)| {
+                result
+                    .as_ref()
+                    .err()
+                    .map(|error| (library.clone(), error.clone()))
+            })
+            .collect()
+    }
+
+    /// Queried libraries and skipped features, for `GenerationReport`
+    pub fn report(&self) -> NativeProbeReport {
+        NativeProbeReport {
+            found: self.found().into_iter().collect(),
+            missing: self.missing().into_iter().collect(),
+            skipped: self.skipped.borrow().clone(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Record results of `probe` into `GenerationReport::native`
+    pub fn record_native_probe(&mut self, probe: &NativeProbe) {
+        let report = probe.report();
+        self.native_probe.found.extend(report.found);
+        self.native_probe.missing.extend(report.missing);
+        self.native_probe.skipped.extend(report.skipped);
+    }
+}
@@ -0,0 +1,85 @@
+/// Line ending used when writing manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    /// Find dominant line ending of text. `Lf` is used when text has no line ending.
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        if crlf > lf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub(crate) fn apply(self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => text,
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// Handling of features used in source, but declared neither as feature nor optional dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownFeaturePolicy {
+    /// Source is not scanned
+    #[default]
+    Ignore,
+    /// Emit `cargo:warning` with file and line for each usage
+    Warn,
+    /// Fail with `Error::UnknownFeatureUsage`
+    Error,
+}
+
+/// Options for writing manifest
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Line ending of written manifest. Line ending of original manifest is kept when `None`.
+    pub line_ending: Option<LineEnding>,
+    /// Emit `cargo:warning` summary of changes(e.g. `3 features added, 1 removed in group 'backends'`) when manifest is written
+    pub warn_summary: bool,
+    /// Thresholds of lints reported with `cargo:warning`. Lints are disabled by default.
+    pub lints: crate::LintOptions,
+    /// Emit `cargo:warning` for each generated feature never referenced in `src`(see `Manifest::unused_generated_features`)
+    pub warn_unused_features: bool,
+    /// Handling of unknown features used in `src`(see `Manifest::unknown_feature_usages`)
+    pub unknown_features: UnknownFeaturePolicy,
+}
+
+/// Options for writing publish-ready manifest
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// Line ending of written manifest. Line ending of original manifest is kept when `None`.
+    pub line_ending: Option<LineEnding>,
+    /// Keep auto-generated marking comments of features
+    pub keep_markers: bool,
+    /// Keep generation state recorded under `[package.metadata.manifest-feature-gen]`
+    pub keep_state: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_line_ending() {
+        assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\r\nb\nc\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn apply_line_ending() {
+        assert_eq!(LineEnding::CrLf.apply("a\nb\r\n"), "a\r\nb\r\n");
+        assert_eq!(LineEnding::Lf.apply("a\r\nb\n"), "a\nb\n");
+    }
+}
@@ -0,0 +1,190 @@
+use toml_edit::{Array, Item, Value};
+
+use crate::{Error, ItemKind, Manifest};
+
+const PACKAGE_TABLE_NAME: &str = "package";
+const LIB_TABLE_NAME: &str = "lib";
+const DESCRIPTION_KEY: &str = "description";
+/// Maximum number of keywords accepted by crates.io
+const MAX_KEYWORDS: usize = 5;
+
+/// List fields of `[package]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageList {
+    Keywords,
+    Categories,
+    Include,
+    Exclude,
+}
+
+impl PackageList {
+    fn key(self) -> &'static str {
+        match self {
+            PackageList::Keywords => "keywords",
+            PackageList::Categories => "categories",
+            PackageList::Include => "include",
+            PackageList::Exclude => "exclude",
+        }
+    }
+}
+
+impl Manifest {
+    /// `package.name`
+    pub fn package_name(&self) -> Result<&str, Error> {
+        let name = self
+            .document
+            .get(PACKAGE_TABLE_NAME)
+            .and_then(|package| package.get("name"));
+        name.and_then(Item::as_str).ok_or_else(|| {
+            Error::malformed(
+                &self.path,
+                &[PACKAGE_TABLE_NAME, "name"],
+                ItemKind::String,
+                name.map(ItemKind::of_item).unwrap_or(ItemKind::None),
+                None,
+            )
+        })
+    }
+
+    /// Crate name used in `use` paths. `lib.name`, or `package.name` with `-` replaced by `_` when not declared.
+    pub fn lib_name(&self) -> Result<String, Error> {
+        let name = self
+            .document
+            .get(LIB_TABLE_NAME)
+            .and_then(|lib| lib.get("name"));
+        match name {
+            None => Ok(self.package_name()?.replace('-', "_")),
+            Some(name) => name.as_str().map(str::to_string).ok_or_else(|| {
+                Error::malformed(
+                    &self.path,
+                    &[LIB_TABLE_NAME, "name"],
+                    ItemKind::String,
+                    ItemKind::of_item(name),
+                    None,
+                )
+            }),
+        }
+    }
+
+    /// Values of list field. Empty when field is not declared.
+    pub fn package_values(&self, list: PackageList) -> Vec<&str> {
+        self.document
+            .get(PACKAGE_TABLE_NAME)
+            .and_then(|package| package.get(list.key()))
+            .and_then(Item::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect()
+    }
+
+    /// Array of list field, created when missing.\
+    /// Fails for field inherited from workspace(`keywords.workspace = true`) or not declared as array.
+    fn package_array_mut(&mut self, list: PackageList) -> Result<&mut Array, Error> {
+        let path = self.path.clone();
+        let item = self.document[PACKAGE_TABLE_NAME][list.key()]
+            .or_insert(Item::Value(Value::Array(Array::new())));
+        let kind = ItemKind::of_item(item);
+        item.as_array_mut().ok_or_else(|| {
+            Error::malformed(
+                &path,
+                &[PACKAGE_TABLE_NAME, list.key()],
+                ItemKind::Array,
+                kind,
+                None,
+            )
+        })
+    }
+
+    /// Add values to list field(e.g. keyword of selected backend). Existing values are not duplicated.\
+    /// Change is written with generated features, and detected same as them. Returns whether field is changed.
+    pub fn add_package_values<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        list: PackageList,
+        values: I,
+    ) -> Result<bool, Error> {
+        let current = self
+            .package_values(list)
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let mut added = Vec::new();
+        for value in values {
+            if !current.iter().any(|current| current == value) && !added.contains(&value) {
+                added.push(value);
+            }
+        }
+        if added.is_empty() {
+            return Ok(false);
+        }
+        if list == PackageList::Keywords && current.len() + added.len() > MAX_KEYWORDS {
+            return Err(Error::TooManyKeywords(
+                current
+                    .into_iter()
+                    .chain(added.into_iter().map(str::to_string))
+                    .collect(),
+            ));
+        }
+
+        let array = self.package_array_mut(list)?;
+        for value in added {
+            array.push(value);
+        }
+        Ok(true)
+    }
+
+    /// Remove values from list field. Returns whether field is changed.
+    pub fn remove_package_values<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        list: PackageList,
+        values: I,
+    ) -> bool {
+        let Some(array) = self
+            .document
+            .get_mut(PACKAGE_TABLE_NAME)
+            .and_then(|package| package.get_mut(list.key()))
+            .and_then(Item::as_array_mut)
+        else {
+            return false;
+        };
+        let values = values.into_iter().collect::<Vec<_>>();
+        let length = array.len();
+        array.retain(|value| !value.as_str().is_some_and(|value| values.contains(&value)));
+        array.len() != length
+    }
+
+    /// Append `suffix` to `package.description`(e.g. ` (OpenGL backend)`), unless it already ends with `suffix`.\
+    /// Returns whether description is changed.
+    pub fn set_description_suffix(&mut self, suffix: &str) -> Result<bool, Error> {
+        let path = self.path.clone();
+        let item = &mut self.document[PACKAGE_TABLE_NAME][DESCRIPTION_KEY];
+        let description = match item.as_str() {
+            Some(description) => description,
+            None if item.is_none() => "",
+            None => {
+                return Err(Error::malformed(
+                    &path,
+                    &[PACKAGE_TABLE_NAME, DESCRIPTION_KEY],
+                    ItemKind::String,
+                    ItemKind::of_item(item),
+                    None,
+                ))
+            }
+        };
+        if description.ends_with(suffix) {
+            return Ok(false);
+        }
+
+        let description = format!("{}{}", description, suffix);
+        match item.as_value_mut() {
+            // keep comments around value
+            Some(value) => {
+                let decor = value.decor().clone();
+                *value = description.into();
+                *value.decor_mut() = decor;
+            }
+            None => *item = Item::Value(description.into()),
+        }
+        Ok(true)
+    }
+}
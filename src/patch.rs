@@ -0,0 +1,278 @@
+use toml_edit::{InlineTable, Item, Table, Value};
+
+use crate::{manifest::AUTO_GENERATE_COMMENT, Error, Manifest, WorkspaceManifest};
+
+const PATCH_TABLE_NAME: &str = "patch";
+const CRATES_IO: &str = "crates-io";
+
+/// `[patch.<registry>]` entry installed only while a feature of group is selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    registry: String,
+    crate_name: String,
+    source: Vec<(&'static str, String)>,
+}
+
+impl Patch {
+    /// Patch `crate_name` of crates.io with crate at `path`(e.g. vendored fork)
+    pub fn path(crate_name: &str, path: &str) -> Self {
+        Self::new(crate_name, "path", path)
+    }
+
+    /// Patch `crate_name` of crates.io with git repository
+    pub fn git(crate_name: &str, url: &str) -> Self {
+        Self::new(crate_name, "git", url)
+    }
+
+    fn new(crate_name: &str, key: &'static str, value: &str) -> Self {
+        Self {
+            registry: CRATES_IO.to_string(),
+            crate_name: crate_name.to_string(),
+            source: vec![(key, value.to_string())],
+        }
+    }
+
+    /// Patch crate of other registry, or source URL
+    pub fn registry(mut self, registry: &str) -> Self {
+        self.registry = registry.to_string();
+        self
+    }
+
+    /// Branch of git repository
+    pub fn branch(mut self, branch: &str) -> Self {
+        self.source.push(("branch", branch.to_string()));
+        self
+    }
+
+    /// Revision of git repository
+    pub fn rev(mut self, rev: &str) -> Self {
+        self.source.push(("rev", rev.to_string()));
+        self
+    }
+
+    /// Name of package in source, when it differs from `crate_name`
+    pub fn package(mut self, package: &str) -> Self {
+        self.source.push(("package", package.to_string()));
+        self
+    }
+
+    /// Whether installed `item` points same source
+    fn matches(&self, item: &Item) -> bool {
+        let Some(table) = item.as_table_like() else {
+            return false;
+        };
+        table.len() == self.source.len()
+            && self
+                .source
+                .iter()
+                .all(|(key, value)| table.get(key).and_then(Item::as_str) == Some(value))
+    }
+
+    fn to_item(&self) -> Item {
+        let mut table = InlineTable::new();
+        for (key, value) in &self.source {
+            table.insert(*key, value.as_str().into());
+        }
+        let mut value = Value::InlineTable(table);
+        value.decor_mut().set_suffix(AUTO_GENERATE_COMMENT);
+        Item::Value(value)
+    }
+}
+
+/// Whether patch entry is installed by this crate
+fn is_generated(item: &Item) -> bool {
+    item.as_value()
+        .and_then(|value| value.decor().suffix())
+        .and_then(|suffix| suffix.as_str())
+        .is_some_and(|suffix| suffix.trim() == AUTO_GENERATE_COMMENT.trim())
+}
+
+impl Manifest {
+    /// Install `patch` while `feature` of `group` is selected, and remove it otherwise.\
+    /// Patch is decided while generating, not while building. So manifest is changed and has to be regenerated
+    /// whenever selection changes, and is reported with `cargo:warning`.\
+    /// Call after features of group are added, as `Error::UnknownFeature` is returned when `feature` is not its member.
+    pub fn add_patch_for_feature(
+        &mut self,
+        group: &str,
+        feature: &str,
+        patch: Patch,
+    ) -> Result<(), Error> {
+        let members = match (
+            self.exclusion_groups.get(group),
+            self.parent_features.get(group),
+        ) {
+            (Some(exclusion_group), _) => &exclusion_group.members,
+            (None, Some(children)) => children,
+            (None, None) => return Err(Error::UnknownGroup(group.to_string())),
+        };
+        if !members.iter().any(|member| member == feature) {
+            return Err(Error::UnknownFeature(feature.to_string()));
+        }
+        self.patches
+            .push((group.to_string(), feature.to_string(), patch));
+        Ok(())
+    }
+
+    /// Install patches of selected features, and remove generated patches of others
+    pub(crate) fn apply_patches(&mut self) -> Result<(), Error> {
+        if self.patches.is_empty() {
+            return Ok(());
+        }
+        if let Some(root) = WorkspaceManifest::locate(&self.path)?.filter(|root| root != &self.path)
+        {
+            println!(
+                "cargo:warning=[patch] of workspace member is ignored by cargo. Declare patches in {}",
+                root.display()
+            );
+        }
+
+        let patches = std::mem::take(&mut self.patches);
+        let patch_table = self.document[PATCH_TABLE_NAME].or_insert({
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        });
+        let Some(patch_table) = patch_table.as_table_like_mut() else {
+            self.patches = patches;
+            return Ok(());
+        };
+
+        for (index, (group, feature, patch)) in patches.iter().enumerate() {
+            let is_first = !patches[..index].iter().any(|(_, _, other)| {
+                other.registry == patch.registry && other.crate_name == patch.crate_name
+            });
+            if !is_first {
+                continue;
+            }
+            // first selected one among patches of same crate
+            let selected = patches[index..]
+                .iter()
+                .filter(|(_, _, other)| {
+                    other.registry == patch.registry && other.crate_name == patch.crate_name
+                })
+                .find(|(_, feature, _)| self.feature_source.is_enabled(feature));
+
+            let Some(registry) = patch_table
+                .entry(&patch.registry)
+                .or_insert(Item::Table(Table::new()))
+                .as_table_like_mut()
+            else {
+                continue;
+            };
+            let installed = registry.get(&patch.crate_name);
+            match selected {
+                Some((group, feature, patch))
+                    if !installed.is_some_and(|installed| patch.matches(installed)) =>
+                {
+                    registry.insert(&patch.crate_name, patch.to_item());
+                    println!(
+                        "cargo:warning=[patch.{}] {} is installed for feature({}) of group({}). Patch is decided while generating, so manifest is regenerated when selection changes",
+                        patch.registry, patch.crate_name, feature, group
+                    );
+                }
+                None if installed.is_some_and(is_generated) => {
+                    registry.remove(&patch.crate_name);
+                    println!(
+                        "cargo:warning=[patch.{}] {} is removed, as feature({}) of group({}) is not selected. Patch is decided while generating, so manifest is regenerated when selection changes",
+                        patch.registry, patch.crate_name, feature, group
+                    );
+                }
+                _ => {}
+            }
+            if registry.is_empty() {
+                patch_table.remove(&patch.registry);
+            }
+        }
+        if patch_table.is_empty() {
+            self.document.remove(PATCH_TABLE_NAME);
+        }
+
+        self.patches = patches;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CfgFeatureSource;
+
+    const SOURCE: &str = "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n";
+
+    /// Manifest written to temporary directory named `test`, as workspace of it is located while applying
+    fn manifest(test: &str, enabled: &[&str]) -> Manifest {
+        let dir = std::env::temp_dir().join(format!("manifest-feature-gen-{}", test));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Cargo.toml");
+        std::fs::write(&path, SOURCE).unwrap();
+        let mut manifest = Manifest::new(path, false).unwrap();
+        manifest.declare_exclusion_group("backend");
+        manifest
+            .add_features_to_exclusion_group("backend", ["gl", "vulkan"].into_iter(), |_, _| {})
+            .unwrap();
+        manifest.set_feature_source(CfgFeatureSource::from_flags(
+            enabled.iter().flat_map(|feature| ["--cfg", *feature]),
+        ));
+        manifest
+            .add_patch_for_feature("backend", "gl", Patch::path("glow", "vendor/glow"))
+            .unwrap();
+        manifest
+            .add_patch_for_feature(
+                "backend",
+                "vulkan",
+                Patch::git("glow", "https://example.com/glow"),
+            )
+            .unwrap();
+        manifest
+    }
+
+    fn installed(manifest: &Manifest) -> Option<String> {
+        manifest
+            .document
+            .get(PATCH_TABLE_NAME)
+            .and_then(|patch| patch.get(CRATES_IO))
+            .and_then(|registry| registry.get("glow"))
+            .map(|item| item.to_string())
+    }
+
+    #[test]
+    fn patch_of_unknown_member() {
+        let mut manifest = manifest("unknown-member-patch", &[]);
+        assert!(matches!(
+            manifest.add_patch_for_feature("backend", "vulcan", Patch::path("glow", "vendor/glow")),
+            Err(Error::UnknownFeature(feature)) if feature == "vulcan"
+        ));
+        assert!(matches!(
+            manifest.add_patch_for_feature("renderer", "gl", Patch::path("glow", "vendor/glow")),
+            Err(Error::UnknownGroup(_))
+        ));
+        std::fs::remove_dir_all(manifest.path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn selected_patch_is_installed() {
+        let mut manifest = manifest("selected-patch", &["vulkan"]);
+        manifest.apply_patches().unwrap();
+        let installed = installed(&manifest).unwrap();
+        assert!(installed.contains("git = \"https://example.com/glow\""));
+        assert!(is_generated(
+            &manifest.document[PATCH_TABLE_NAME][CRATES_IO]["glow"]
+        ));
+    }
+
+    #[test]
+    fn unselected_patch_is_removed() {
+        let mut manifest = manifest("unselected-patch", &["gl"]);
+        manifest.apply_patches().unwrap();
+        assert!(installed(&manifest)
+            .unwrap()
+            .contains("path = \"vendor/glow\""));
+
+        manifest.set_feature_source(CfgFeatureSource::from_flags(std::iter::empty()));
+        manifest.apply_patches().unwrap();
+        assert_eq!(installed(&manifest), None);
+        assert!(manifest.document.get(PATCH_TABLE_NAME).is_none());
+        std::fs::remove_dir_all(manifest.path().parent().unwrap()).unwrap();
+    }
+}
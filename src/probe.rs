@@ -0,0 +1,240 @@
+use std::{
+    ffi::OsString,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use crate::Error;
+
+/// Version of rustc
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RustcVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub channel: RustcChannel,
+}
+
+impl std::fmt::Display for RustcVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Release channel of rustc
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RustcChannel {
+    Nightly,
+    Dev,
+    Beta,
+    Stable,
+}
+
+impl RustcVersion {
+    /// Parse output of `rustc --version`. (e.g. `rustc 1.80.0-nightly (...)`)
+    pub fn parse(output: &str) -> Option<Self> {
+        let version = output.split_whitespace().nth(1)?;
+        let (numbers, channel) = match version.split_once('-') {
+            Some((numbers, channel)) => (numbers, channel),
+            None => (version, ""),
+        };
+        let channel = match channel {
+            "" => RustcChannel::Stable,
+            "dev" => RustcChannel::Dev,
+            "nightly" => RustcChannel::Nightly,
+            channel if channel.starts_with("beta") => RustcChannel::Beta,
+            _ => return None,
+        };
+        let mut numbers = numbers.split('.').map(str::parse::<u32>);
+        Some(Self {
+            major: numbers.next()?.ok()?,
+            minor: numbers.next()?.ok()?,
+            patch: numbers.next().unwrap_or(Ok(0)).ok()?,
+            channel,
+        })
+    }
+
+    /// Parse `rust-version` of manifest(e.g. `1.70`, `1.70.1`) as stable version
+    pub fn parse_rust_version(rust_version: &str) -> Option<Self> {
+        let mut numbers = rust_version.trim().split('.').map(str::parse::<u32>);
+        let version = Self {
+            major: numbers.next()?.ok()?,
+            minor: numbers.next()?.ok()?,
+            patch: numbers.next().unwrap_or(Ok(0)).ok()?,
+            channel: RustcChannel::Stable,
+        };
+        numbers.next().is_none().then_some(version)
+    }
+
+    /// Whether this version is at least `major.minor`
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    /// Whether unstable features can be used
+    pub fn is_nightly(&self) -> bool {
+        matches!(self.channel, RustcChannel::Nightly | RustcChannel::Dev)
+    }
+}
+
+/// autocfg-style probe of compiler capabilities, for deciding generated features
+pub struct RustcProbe {
+    rustc: OsString,
+    wrapper: Option<OsString>,
+    out_dir: PathBuf,
+    target: Option<OsString>,
+    rustflags: Vec<String>,
+    version: Option<RustcVersion>,
+}
+
+impl RustcProbe {
+    /// Probe `rustc`, compiling probes in `out_dir`
+    pub fn new(rustc: OsString, out_dir: PathBuf, target: Option<OsString>) -> Self {
+        Self {
+            rustc,
+            wrapper: None,
+            out_dir,
+            target,
+            rustflags: Vec::new(),
+            version: None,
+        }
+    }
+
+    /// Probe compiler of current build, and emit `cargo:rerun-if-env-changed` for it.\
+    /// `RUSTC_WRAPPER` and flags of `CARGO_ENCODED_RUSTFLAGS` are used same as cargo does.
+    pub fn new_with_env() -> Result<Self, Error> {
+        println!("cargo:rerun-if-env-changed=RUSTC");
+        println!("cargo:rerun-if-env-changed=RUSTC_WRAPPER");
+        println!("cargo:rerun-if-env-changed=CARGO_ENCODED_RUSTFLAGS");
+        let mut probe = Self::new(
+            std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()),
+            crate::env::var_path("OUT_DIR")?,
+            std::env::var_os("TARGET"),
+        );
+        probe.wrapper = std::env::var_os("RUSTC_WRAPPER").filter(|wrapper| !wrapper.is_empty());
+        if let Some(flags) = crate::env::var("CARGO_ENCODED_RUSTFLAGS")? {
+            probe = probe.rustflags(flags.split('\x1f'));
+        }
+        Ok(probe)
+    }
+
+    /// Invoke compiler through `wrapper`(e.g. `sccache`), as `RUSTC_WRAPPER` of cargo
+    pub fn wrapper(mut self, wrapper: OsString) -> Self {
+        self.wrapper = Some(wrapper);
+        self
+    }
+
+    /// Pass `flags` when compiling probes(e.g. `--cap-lints`, target features). Empty flags are ignored.
+    pub fn rustflags<'a, I: IntoIterator<Item = &'a str>>(mut self, flags: I) -> Self {
+        self.rustflags.extend(
+            flags
+                .into_iter()
+                .filter(|flag| !flag.is_empty())
+                .map(str::to_string),
+        );
+        self
+    }
+
+    /// Command invoking compiler, through wrapper if any
+    fn command(&self) -> Command {
+        match &self.wrapper {
+            Some(wrapper) => {
+                let mut command = Command::new(wrapper);
+                command.arg(&self.rustc);
+                command
+            }
+            None => Command::new(&self.rustc),
+        }
+    }
+
+    /// Version of compiler
+    pub fn version(&mut self) -> Result<RustcVersion, Error> {
+        if let Some(version) = self.version {
+            return Ok(version);
+        }
+
+        let output = self.command().arg("--version").output()?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let version = RustcVersion::parse(&output)
+            .ok_or_else(|| Error::RustcVersionError(output.to_string()))?;
+        self.version = Some(version);
+        Ok(version)
+    }
+
+    /// Whether `code` compiles as library crate
+    pub fn probe(&self, code: &str) -> Result<bool, Error> {
+        let mut command = self.command();
+        command
+            .args(&self.rustflags)
+            .args([
+                "--crate-name",
+                "manifest_feature_gen_probe",
+                "--crate-type=lib",
+            ])
+            .args(["--emit=metadata", "--out-dir"])
+            .arg(&self.out_dir)
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(target) = &self.target {
+            command.arg("--target").arg(target);
+        }
+
+        let mut child = command.spawn()?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(code.as_bytes())?;
+        Ok(child.wait()?.success())
+    }
+
+    /// Whether `expression` is valid
+    pub fn probe_expression(&self, expression: &str) -> Result<bool, Error> {
+        self.probe(&format!("pub fn probe() {{ let _ = {}; }}", expression))
+    }
+
+    /// Whether `path` exists
+    pub fn probe_path(&self, path: &str) -> Result<bool, Error> {
+        self.probe(&format!("pub use {};", path))
+    }
+
+    /// Whether unstable `feature` can be enabled
+    pub fn probe_unstable_feature(&self, feature: &str) -> Result<bool, Error> {
+        self.probe(&format!("#![feature({})]", feature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version() {
+        let version = RustcVersion::parse("rustc 1.80.1 (3f5fd8dd4 2024-08-06)").unwrap();
+        assert_eq!(version.to_string(), "1.80.1");
+        assert_eq!(version.channel, RustcChannel::Stable);
+        assert!(version.at_least(1, 80) && !version.at_least(1, 81));
+
+        let version = RustcVersion::parse("rustc 1.82.0-nightly (1f12b9b0f 2024-08-14)").unwrap();
+        assert!(version.is_nightly());
+        assert_eq!(
+            RustcVersion::parse("rustc 1.81.0-beta.5").map(|version| version.channel),
+            Some(RustcChannel::Beta)
+        );
+        assert_eq!(RustcVersion::parse("rustc 1.80.0-unknown"), None);
+        assert_eq!(RustcVersion::parse("rustc"), None);
+    }
+
+    #[test]
+    fn parse_rust_version() {
+        assert_eq!(
+            RustcVersion::parse_rust_version("1.70").map(|version| version.to_string()),
+            Some("1.70.0".to_string())
+        );
+        assert!(RustcVersion::parse_rust_version("1.70.1.2").is_none());
+        assert!(RustcVersion::parse_rust_version("1").is_none());
+    }
+}
@@ -0,0 +1,131 @@
+use toml_edit::{Item, Table, Value};
+
+use crate::{Error, Manifest};
+
+const PROFILE_TABLE_NAME: &str = "profile";
+/// Keys accepted in `[profile.*]`
+const PROFILE_KEYS: &[&str] = &[
+    "opt-level",
+    "debug",
+    "split-debuginfo",
+    "strip",
+    "debug-assertions",
+    "overflow-checks",
+    "lto",
+    "panic",
+    "incremental",
+    "codegen-units",
+    "rpath",
+    "inherits",
+];
+/// Keys not accepted in package overrides and `build-override`
+const PROFILE_ONLY_KEYS: &[&str] = &["lto", "panic", "rpath", "inherits"];
+
+/// Part of profile to be adjusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileScope<'a> {
+    /// `[profile.<name>]`
+    Profile,
+    /// `[profile.<name>.package.<package>]`. `*` means all dependencies.
+    Package(&'a str),
+    /// `[profile.<name>.build-override]`
+    BuildOverride,
+}
+
+impl<'a> ProfileScope<'a> {
+    fn check_key(self, key: &str) -> Result<(), Error> {
+        let unsupported = !PROFILE_KEYS.contains(&key)
+            || (self != ProfileScope::Profile && PROFILE_ONLY_KEYS.contains(&key));
+        if unsupported {
+            Err(Error::UnsupportedProfileKey(key.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn path(self, profile: &'a str) -> Vec<&'a str> {
+        match self {
+            ProfileScope::Profile => vec![PROFILE_TABLE_NAME, profile],
+            ProfileScope::Package(package) => vec![PROFILE_TABLE_NAME, profile, "package", package],
+            ProfileScope::BuildOverride => vec![PROFILE_TABLE_NAME, profile, "build-override"],
+        }
+    }
+}
+
+impl Manifest {
+    /// Current value of profile setting
+    pub fn profile_value(
+        &self,
+        profile: &str,
+        scope: ProfileScope<'_>,
+        key: &str,
+    ) -> Option<&Value> {
+        scope
+            .path(profile)
+            .into_iter()
+            .try_fold(self.document.as_item(), |item, key| item.get(key))?
+            .get(key)?
+            .as_value()
+    }
+
+    /// Set profile setting(e.g. `opt-level = 3` for `ProfileScope::Package("image")`).\
+    /// Change is written with generated features, and detected same as them. Returns whether value is changed.
+    ///
+    /// Fails with `Error::UnsupportedProfileKey` for keys cargo does not accept in the scope.
+    pub fn set_profile_value<V: Into<Value>>(
+        &mut self,
+        profile: &str,
+        scope: ProfileScope<'_>,
+        key: &str,
+        value: V,
+    ) -> Result<bool, Error> {
+        scope.check_key(key)?;
+        let value = value.into();
+        if self
+            .profile_value(profile, scope, key)
+            .is_some_and(|current| current.to_string().trim() == value.to_string().trim())
+        {
+            return Ok(false);
+        }
+
+        let path = scope.path(profile);
+        let mut table = self.document.as_table_mut();
+        for (depth, name) in path.iter().enumerate() {
+            let item = table.entry(name).or_insert_with(|| {
+                let mut table = Table::new();
+                // only the innermost table is rendered as header
+                table.set_implicit(depth + 1 < path.len());
+                Item::Table(table)
+            });
+            table = item
+                .as_table_mut()
+                .ok_or_else(|| Error::UnsupportedProfileKey(path[..=depth].join(".")))?;
+        }
+        table.insert(key, Item::Value(value));
+        Ok(true)
+    }
+
+    /// Remove profile setting. Emptied tables are also removed. Returns whether value is removed.
+    pub fn remove_profile_value(
+        &mut self,
+        profile: &str,
+        scope: ProfileScope<'_>,
+        key: &str,
+    ) -> bool {
+        fn remove(table: &mut Table, path: &[&str], key: &str) -> bool {
+            let Some((name, rest)) = path.split_first() else {
+                return table.remove(key).is_some();
+            };
+            let Some(child) = table.get_mut(name).and_then(Item::as_table_mut) else {
+                return false;
+            };
+            let removed = remove(child, rest, key);
+            if removed && child.is_empty() {
+                table.remove(name);
+            }
+            removed
+        }
+
+        remove(self.document.as_table_mut(), &scope.path(profile), key)
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use toml_edit::Item;
+
+use crate::{
+    manifest::{generation_mark, FEATURES_TABLE_NAME},
+    Error, GenerationState, Manifest, PublishOptions,
+};
+
+impl Manifest {
+    /// Write publish-ready variant of manifest to `path`, for packaging pipelines.\
+    /// Generation is applied same as `write`, and generation-only scaffolding is stripped as `options` specify.
+    /// Manifest itself is not written. Call after all add calls.
+    pub fn write_publish_manifest(
+        &mut self,
+        path: &Path,
+        options: &PublishOptions,
+    ) -> Result<(), Error> {
+        self.apply_generation()?;
+
+        let mut document = self.document.clone();
+        if !options.keep_state {
+            GenerationState::strip(&mut document);
+        }
+        if !options.keep_markers {
+            if let Some(features) = document
+                .get_mut(FEATURES_TABLE_NAME)
+                .and_then(Item::as_table_like_mut)
+            {
+                for (_, feature) in features.iter_mut() {
+                    if generation_mark(feature).is_some() {
+                        if let Some(array) = feature.as_array_mut() {
+                            array.decor_mut().set_suffix("");
+                        }
+                    }
+                }
+            }
+        }
+
+        let line_ending = options.line_ending.unwrap_or(self.line_ending);
+        std::fs::write(path, line_ending.apply(&document.to_string()))
+            .map_err(|e| Error::from(e).at_manifest(path))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use toml_edit::{Item, Table, Value};
+
+use crate::{manifest::FEATURES_TABLE_NAME, Manifest};
+
+/// Features of `[features]` table in `document`, with their dependencies
+pub(crate) fn features_of(document: &Table) -> impl Iterator<Item = (&str, Vec<&str>)> {
+    document
+        .get(FEATURES_TABLE_NAME)
+        .and_then(Item::as_table)
+        .into_iter()
+        .flat_map(|features| features.iter())
+        .map(|(feature, deps)| (feature, dependencies_of(deps)))
+}
+
+fn dependencies_of(deps: &Item) -> Vec<&str> {
+    deps.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+pub(crate) fn feature_deps_of<'a>(document: &'a Table, name: &str) -> Option<Vec<&'a str>> {
+    document
+        .get(FEATURES_TABLE_NAME)
+        .and_then(|features| features.get(name))
+        .map(dependencies_of)
+}
+
+impl Manifest {
+    /// Iterate current features, including generated ones, with their dependencies
+    pub fn features(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+        features_of(self.document.as_table())
+    }
+
+    /// Whether feature is declared currently
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.feature_deps(name).is_some()
+    }
+
+    /// Dependencies of feature, if declared
+    pub fn feature_deps(&self, name: &str) -> Option<Vec<&str>> {
+        feature_deps_of(self.document.as_table(), name)
+    }
+
+    /// Every feature enabled for current build, including hand-written ones and optional dependencies.\
+    /// Features not declared in manifest are reported as `FeatureSource` decides.
+    pub fn enabled_features(&self) -> Vec<String> {
+        let optional_dependencies = self
+            .dependency_entries()
+            .into_iter()
+            .filter(|(_, item)| crate::dependencies::is_optional_entry(item))
+            .map(|(name, _)| name);
+        let candidates = self
+            .features()
+            .map(|(feature, _)| feature)
+            .chain(optional_dependencies)
+            .collect::<Vec<_>>();
+        self.feature_source.enabled_features(&candidates)
+    }
+
+    /// Same as `enabled_features`, parsed with `FromFeatureName`. Features not parsed are skipped.
+    pub fn enabled_features_as<T: crate::FromFeatureName>(&self) -> Vec<T> {
+        self.enabled_features()
+            .iter()
+            .filter_map(|feature| T::from_feature_name(feature))
+            .collect()
+    }
+}
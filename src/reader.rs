@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::ImDocument;
+
+use crate::{
+    dependencies::{dependency_entries_of, is_optional_entry},
+    query::{feature_deps_of, features_of},
+    Error, Manifest,
+};
+
+/// Read-only cargo manifest for querying features and dependencies.
+///
+/// Unlike `Manifest`, generated features are kept and nothing can be written.
+pub struct ManifestReader {
+    path: PathBuf,
+    document: ImDocument<String>,
+}
+
+impl ManifestReader {
+    /// Load cargo manifest from specified path
+    pub fn new(path: PathBuf) -> Result<Self, Error> {
+        let source =
+            std::fs::read_to_string(&path).map_err(|e| Error::from(e).at_manifest(&path))?;
+        let document = ImDocument::parse(source).map_err(|e| Error::from(e).at_manifest(&path))?;
+        // Validate shape of features, same as `Manifest`
+        Manifest::collect_features(&path, document.as_table(), Some(document.raw()))?;
+        Ok(Self { path, document })
+    }
+
+    /// Load cargo manifest of current crate
+    pub fn new_with_env() -> Result<Self, Error> {
+        Self::new(crate::env::manifest_path()?)
+    }
+
+    /// Path of loaded manifest
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Iterate features with their dependencies
+    pub fn features(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+        features_of(self.document.as_table())
+    }
+
+    /// Whether feature is declared
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.feature_deps(name).is_some()
+    }
+
+    /// Dependencies of feature, if declared
+    pub fn feature_deps(&self, name: &str) -> Option<Vec<&str>> {
+        feature_deps_of(self.document.as_table(), name)
+    }
+
+    /// Names of dependencies which features can refer, with whether they are optional
+    pub fn dependencies(&self) -> impl Iterator<Item = (&str, bool)> {
+        dependency_entries_of(self.document.as_table())
+            .into_iter()
+            .map(|(name, item)| (name, is_optional_entry(item)))
+    }
+
+    /// Whether dependency is declared
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.dependencies()
+            .any(|(dependency, _)| dependency == name)
+    }
+
+    /// Whether dependency is declared as optional
+    pub fn is_optional_dependency(&self, name: &str) -> bool {
+        self.dependencies()
+            .any(|(dependency, optional)| dependency == name && optional)
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{Error, FeatureName, Manifest};
+
+/// Changes of features belonging to a group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureChanges {
+    pub added: Vec<FeatureName>,
+    /// Features whose dependencies are changed
+    pub updated: Vec<FeatureName>,
+    pub removed: Vec<FeatureName>,
+    pub unchanged: Vec<FeatureName>,
+}
+
+impl FeatureChanges {
+    /// Whether any feature is added, updated or removed
+    pub fn is_changed(&self) -> bool {
+        !(self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty())
+    }
+}
+
+/// Results of `NativeProbe`, recorded with `Manifest::record_native_probe`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NativeProbeReport {
+    /// Libraries found, with their versions
+    pub found: BTreeMap<String, String>,
+    /// Libraries not found, with diagnostics of pkg-config
+    pub missing: BTreeMap<String, String>,
+    /// Skipped features, with native libraries missing for them
+    pub skipped: BTreeMap<String, Vec<String>>,
+}
+
+/// Summary of generation, returned by `Manifest::write_with_report`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationReport {
+    /// Changes of features by named group(exclusion group or parent feature).\
+    /// Features not belonging to any named group are listed under empty name.
+    pub changes: BTreeMap<String, FeatureChanges>,
+    /// Enabled features of each named exclusion group
+    pub chosen: BTreeMap<String, Vec<String>>,
+    /// Feature chosen by target for each named exclusion group without enabled member
+    pub target_defaults: BTreeMap<String, String>,
+    /// Cargo directives emitted by registered exports, and paths of files written by them
+    pub exports: Vec<String>,
+    /// Minimum rust-version required by each generated feature, registered with `Manifest::require_rust_version`
+    pub rust_versions: BTreeMap<String, String>,
+    /// Lints found with `WriteOptions::lints`
+    pub lints: Vec<crate::FeatureLint>,
+    /// Generated features never referenced in source, scanned with `WriteOptions::warn_unused_features`
+    pub unused_features: Vec<FeatureName>,
+    /// Usages of unknown features in source, scanned with `WriteOptions::unknown_features`
+    pub unknown_usages: Vec<crate::FeatureUsage>,
+    /// Native libraries probed, recorded with `Manifest::record_native_probe`
+    pub native: NativeProbeReport,
+    /// Aliases(`Manifest::add_feature_aliases`) and deprecated features(`Manifest::deprecate_feature`)
+    /// mapped to their replacements. Deprecated features without replacement are not listed.
+    pub deprecated: BTreeMap<String, String>,
+    /// Mutually exclusive groups breaking under feature unification, found by `Manifest::unification_hazards`
+    pub unification_hazards: Vec<crate::UnificationHazard>,
+    /// Whether manifest is written
+    pub written: bool,
+}
+
+impl GenerationReport {
+    /// Whether any feature is added, updated or removed
+    pub fn is_changed(&self) -> bool {
+        self.changes.values().any(FeatureChanges::is_changed)
+    }
+
+    /// One line summary for each changed group(e.g. `3 features added, 1 removed in group 'backends'`)
+    pub fn summary(&self) -> Vec<String> {
+        self.changes
+            .iter()
+            .filter(|(_, changes)| changes.is_changed())
+            .map(|(group, changes)| {
+                let counts = [
+                    (changes.added.len(), "added"),
+                    (changes.updated.len(), "updated"),
+                    (changes.removed.len(), "removed"),
+                ]
+                .into_iter()
+                .filter(|(count, _)| *count > 0)
+                .enumerate()
+                .map(|(index, (count, action))| {
+                    if index == 0 {
+                        let noun = if count == 1 { "feature" } else { "features" };
+                        format!("{} {} {}", count, noun, action)
+                    } else {
+                        format!("{} {}", count, action)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+                if group.is_empty() {
+                    counts
+                } else {
+                    format!("{} in group '{}'", counts, group)
+                }
+            })
+            .collect()
+    }
+}
+
+impl Manifest {
+    /// Report of features compared with loaded manifest. `written` is not set.
+    pub(crate) fn generation_report(&self) -> Result<GenerationReport, Error> {
+        let current = Self::collect_features(&self.path, self.document.as_table(), None)?;
+        let group_of = self
+            .exclusion_groups
+            .iter()
+            .flat_map(|(group, exclusion_group)| {
+                exclusion_group
+                    .members
+                    .iter()
+                    .map(move |member| (member.as_str(), group.as_str()))
+            })
+            .chain(self.parent_features.iter().flat_map(|(parent, children)| {
+                children
+                    .iter()
+                    .map(move |child| (child.as_str(), parent.as_str()))
+            }))
+            .chain(
+                self.previous_groups
+                    .iter()
+                    .map(|(feature, group)| (feature.as_str(), group.as_str())),
+            )
+            .fold(HashMap::new(), |mut group_of, (feature, group)| {
+                group_of.entry(feature).or_insert(group);
+                group_of
+            });
+
+        let mut report = GenerationReport::default();
+        let mut names = current
+            .keys()
+            .chain(self.original_features.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        names.sort();
+        for feature in names {
+            let changes = report
+                .changes
+                .entry(
+                    group_of
+                        .get(feature.as_str())
+                        .copied()
+                        .unwrap_or_default()
+                        .to_string(),
+                )
+                .or_default();
+            let name = FeatureName::new_unchecked(feature.clone());
+            match (self.original_features.get(feature), current.get(feature)) {
+                (None, Some(_)) => changes.added.push(name),
+                (Some(_), None) => changes.removed.push(name),
+                (Some(original), Some(current)) if original != current => {
+                    changes.updated.push(name)
+                }
+                _ => changes.unchanged.push(name),
+            }
+        }
+
+        report.chosen = self
+            .exclusion_groups
+            .iter()
+            .map(|(group, exclusion_group)| (group.clone(), exclusion_group.enabled.clone()))
+            .collect();
+        for group in self.exclusion_groups.keys() {
+            if let Some(default) = self.target_default_of(group)? {
+                report.target_defaults.insert(group.clone(), default);
+            }
+        }
+        report.rust_versions = self
+            .required_rust_versions()
+            .map(|(feature, version)| (feature.to_string(), version.to_string()))
+            .collect();
+        report.native = self.native_probe.clone();
+        report.deprecated = self
+            .feature_aliases()
+            .iter()
+            .cloned()
+            .chain(
+                self.deprecated_features()
+                    .iter()
+                    .filter_map(|(feature, replacement)| {
+                        Some((feature.clone(), replacement.clone()?))
+                    }),
+            )
+            .collect();
+        report.unification_hazards = self.unification_hazards(true);
+
+        Ok(report)
+    }
+}
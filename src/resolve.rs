@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use crate::Manifest;
+
+/// Features enabled transitively, computed by `Manifest::resolve`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResolvedSet {
+    /// Features of `[features]` table
+    pub features: BTreeSet<String>,
+    /// Optional dependencies enabled(`dep:foo`, implicit feature or `foo/bar`)
+    pub dependencies: BTreeSet<String>,
+    /// Features of dependencies enabled(e.g. `foo/bar`). Weak ones(`foo?/bar`) are included only when `foo` is enabled.
+    pub crate_features: BTreeSet<String>,
+    /// Names neither declared as feature nor optional dependency
+    pub unknown: BTreeSet<String>,
+}
+
+impl ResolvedSet {
+    /// Whether feature is enabled
+    pub fn contains(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Iterate enabled features
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().map(String::as_str)
+    }
+}
+
+impl Manifest {
+    /// Compute features enabled transitively by `enabled`, following `[features]` table including generated features.
+    pub fn resolve<S: AsRef<str>>(&self, enabled: &[S]) -> ResolvedSet {
+        let optional_dependencies = self
+            .dependency_entries()
+            .into_iter()
+            .filter(|(_, item)| crate::dependencies::is_optional_entry(item))
+            .map(|(name, _)| name)
+            .collect::<BTreeSet<_>>();
+
+        let mut resolved = ResolvedSet::default();
+        let mut weak = Vec::new();
+        let mut pending = enabled
+            .iter()
+            .map(|feature| feature.as_ref().to_string())
+            .collect::<Vec<_>>();
+        while let Some(name) = pending.pop() {
+            if let Some(crate_name) = name.strip_prefix("dep:") {
+                resolved.dependencies.insert(crate_name.to_string());
+            } else if let Some((crate_name, feature)) = name.split_once('/') {
+                match crate_name.strip_suffix('?') {
+                    Some(crate_name) => weak.push((crate_name.to_string(), feature.to_string())),
+                    None => {
+                        if optional_dependencies.contains(crate_name) {
+                            resolved.dependencies.insert(crate_name.to_string());
+                        }
+                        resolved.crate_features.insert(name.clone());
+                    }
+                }
+            } else if let Some(deps) = self.feature_deps(&name) {
+                if resolved.features.insert(name.clone()) {
+                    pending.extend(deps.into_iter().map(str::to_string));
+                }
+            } else if optional_dependencies.contains(name.as_str()) {
+                resolved.dependencies.insert(name);
+            } else {
+                resolved.unknown.insert(name);
+            }
+        }
+
+        for (crate_name, feature) in weak {
+            if resolved.dependencies.contains(&crate_name)
+                || !optional_dependencies.contains(crate_name.as_str())
+            {
+                resolved
+                    .crate_features
+                    .insert(format!("{}/{}", crate_name, feature));
+            }
+        }
+        resolved
+    }
+}
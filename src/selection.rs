@@ -0,0 +1,413 @@
+use std::path::PathBuf;
+
+use toml_edit::ImDocument;
+
+use crate::{Error, FromFeatureName, ItemKind, SourceLocation};
+
+/// Source deciding which features are enabled for current build.
+///
+/// By default, `CARGO_FEATURE_*` variables set by cargo are used.
+pub trait FeatureSource {
+    /// Whether feature is enabled
+    fn is_enabled(&self, feature: &str) -> bool;
+
+    /// Every enabled feature. Each one is mapped back to one of `candidates` when possible.
+    fn enabled_features(&self, candidates: &[&str]) -> Vec<String>;
+
+    /// Same as `enabled_features`, parsed with `FromFeatureName`. Features not parsed are skipped.
+    fn enabled_features_as<T: FromFeatureName>(&self, candidates: &[&str]) -> Vec<T>
+    where
+        Self: Sized,
+    {
+        self.enabled_features(candidates)
+            .iter()
+            .filter_map(|feature| T::from_feature_name(feature))
+            .collect()
+    }
+
+    /// Validate selection against generated features, while writing manifest.\
+    /// Nothing is validated by default.
+    fn validate(&self, _generated: &[&str]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Default mangling of cargo. `-` is replaced with `_`, and name is uppercased.
+pub fn cargo_env_mangling(feature: &str) -> String {
+    feature.replace('-', "_").to_uppercase()
+}
+
+/// Features enabled by environment variables named `{prefix}{mangled feature}`
+pub struct EnvFeatureSource {
+    prefix: String,
+    mangling: Box<dyn Fn(&str) -> String>,
+}
+
+impl Default for EnvFeatureSource {
+    fn default() -> Self {
+        Self::with_prefix("CARGO_FEATURE_")
+    }
+}
+
+impl EnvFeatureSource {
+    /// Source of `CARGO_FEATURE_*` variables set by cargo
+    pub fn cargo() -> Self {
+        Self::default()
+    }
+
+    /// Source of variables with custom prefix(e.g. `XTASK_FEATURE_`), mangled same as cargo
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            mangling: Box::new(cargo_env_mangling),
+        }
+    }
+
+    /// Replace mangling of feature name into variable name suffix
+    pub fn mangling<F: Fn(&str) -> String + 'static>(mut self, mangling: F) -> Self {
+        self.mangling = Box::new(mangling);
+        self
+    }
+
+    fn env_name(&self, feature: &str) -> String {
+        format!("{}{}", self.prefix, (self.mangling)(feature))
+    }
+}
+
+impl FeatureSource for EnvFeatureSource {
+    fn is_enabled(&self, feature: &str) -> bool {
+        crate::env::is_set(self.env_name(feature))
+    }
+
+    /// Variables not matching any candidate are reported with lowercased name after prefix
+    fn enabled_features(&self, candidates: &[&str]) -> Vec<String> {
+        let mut unmatched = std::env::vars_os()
+            .filter_map(|(key, _)| key.into_string().ok())
+            .filter(|key| key.starts_with(&self.prefix))
+            .collect::<Vec<_>>();
+        let mut enabled = candidates
+            .iter()
+            .filter(|candidate| {
+                let key = self.env_name(candidate);
+                match unmatched.iter().position(|env| env == &key) {
+                    Some(index) => {
+                        unmatched.swap_remove(index);
+                        true
+                    }
+                    None => false,
+                }
+            })
+            .map(|candidate| candidate.to_string())
+            .collect::<Vec<_>>();
+        enabled.extend(
+            unmatched
+                .iter()
+                .map(|key| key[self.prefix.len()..].to_lowercase()),
+        );
+        enabled.sort();
+        enabled.dedup();
+        enabled
+    }
+}
+
+/// Features enabled by `--cfg` flags of `CARGO_ENCODED_RUSTFLAGS` or `RUSTFLAGS`.
+///
+/// By default, feature is enabled by bare cfg of the same name, with `-` replaced to `_`(`--cfg backend_gl`).\
+/// With `with_key`, feature is enabled by key-value cfg(`--cfg backend="gl"`).
+#[derive(Debug, Clone, Default)]
+pub struct CfgFeatureSource {
+    cfgs: Vec<String>,
+    key: Option<String>,
+}
+
+impl CfgFeatureSource {
+    /// Read flags of current build.\
+    /// `CARGO_ENCODED_RUSTFLAGS` is preferred, as it is set by cargo for build scripts.
+    pub fn new() -> Result<Self, Error> {
+        Ok(match crate::env::var("CARGO_ENCODED_RUSTFLAGS")? {
+            Some(flags) => Self::from_flags(flags.split('\x1f')),
+            None => Self::from_flags(
+                crate::env::var("RUSTFLAGS")?
+                    .unwrap_or_default()
+                    .split_whitespace(),
+            ),
+        })
+    }
+
+    /// Read `--cfg` from specified flags
+    pub fn from_flags<'a, I: Iterator<Item = &'a str>>(flags: I) -> Self {
+        let mut cfgs = Vec::new();
+        let mut flags = flags;
+        while let Some(flag) = flags.next() {
+            if let Some(cfg) = flag.strip_prefix("--cfg=") {
+                cfgs.push(cfg.to_string());
+            } else if flag == "--cfg" {
+                cfgs.extend(flags.next().map(str::to_string));
+            }
+        }
+        Self { cfgs, key: None }
+    }
+
+    /// Match key-value cfg of `key` instead of bare cfg
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    /// Feature enabled by each cfg
+    fn features(&self) -> impl Iterator<Item = String> + '_ {
+        self.cfgs.iter().filter_map(|cfg| match &self.key {
+            Some(key) => {
+                let (name, value) = cfg.split_once('=')?;
+                (name.trim() == key).then(|| value.trim().trim_matches('"').to_string())
+            }
+            None => (!cfg.contains('=')).then(|| cfg.trim().to_string()),
+        })
+    }
+}
+
+impl FeatureSource for CfgFeatureSource {
+    fn is_enabled(&self, feature: &str) -> bool {
+        let mangled = feature.replace('-', "_");
+        self.features()
+            .any(|enabled| enabled == feature || enabled == mangled)
+    }
+
+    fn enabled_features(&self, candidates: &[&str]) -> Vec<String> {
+        let mut enabled = self
+            .features()
+            .map(|enabled| {
+                candidates
+                    .iter()
+                    .find(|candidate| candidate.replace('-', "_") == enabled)
+                    .map(|candidate| candidate.to_string())
+                    .unwrap_or(enabled)
+            })
+            .collect::<Vec<_>>();
+        enabled.sort();
+        enabled.dedup();
+        enabled
+    }
+}
+
+/// Split comma-separated list, ignoring surrounding whitespace and empty items
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Features enabled by project-local config file(e.g. `feature-config.toml`).
+///
+/// ```toml
+/// features = ["board-nrf52", "log-rtt"]
+/// ```
+///
+/// Listed features are replaced with comma-separated value of override variable, when it is set.
+#[derive(Debug, Clone)]
+pub struct ConfigFileFeatureSource {
+    path: PathBuf,
+    override_env: Option<String>,
+    features: Vec<String>,
+}
+
+const CONFIG_FEATURES_KEY: &str = "features";
+
+impl ConfigFileFeatureSource {
+    /// Read config file at `path`. Missing file is considered as empty list.
+    pub fn new(path: PathBuf) -> Result<Self, Error> {
+        let features = match std::fs::read_to_string(&path) {
+            Ok(source) => {
+                let document = ImDocument::parse(source.as_str())
+                    .map_err(|e| Error::from(e).parsing_manifest(&path, &source))?;
+                match document.get(CONFIG_FEATURES_KEY) {
+                    Some(item) => {
+                        let array = item.as_array().ok_or_else(|| {
+                            Error::malformed(
+                                &path,
+                                &[CONFIG_FEATURES_KEY],
+                                ItemKind::Array,
+                                ItemKind::of_item(item),
+                                SourceLocation::locate(Some(&source), item.span()),
+                            )
+                        })?;
+                        array
+                            .iter()
+                            .enumerate()
+                            .map(|(index, value)| {
+                                value.as_str().map(str::to_string).ok_or_else(|| {
+                                    Error::malformed(
+                                        &path,
+                                        &[CONFIG_FEATURES_KEY, &index.to_string()],
+                                        ItemKind::String,
+                                        ItemKind::of_value(value),
+                                        SourceLocation::locate(Some(&source), value.span()),
+                                    )
+                                })
+                            })
+                            .collect::<Result<_, _>>()?
+                    }
+                    None => Vec::new(),
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::from(e).at_manifest(&path)),
+        };
+
+        Ok(Self {
+            path,
+            override_env: None,
+            features,
+        })
+    }
+
+    /// Read config file named `file_name` in `CARGO_MANIFEST_DIR`
+    pub fn new_with_env(file_name: &str) -> Result<Self, Error> {
+        let mut path = crate::env::var_path("CARGO_MANIFEST_DIR")?;
+        path.push(file_name);
+        Self::new(path)
+    }
+
+    /// Replace listed features with comma-separated value of `key`(e.g. `BOARD_FEATURES=a,b`), when it is set
+    pub fn env_override(mut self, key: &str) -> Result<Self, Error> {
+        if let Some(list) = crate::env::var(key)? {
+            self.features = split_list(&list);
+        }
+        self.override_env = Some(key.to_string());
+        Ok(self)
+    }
+
+    /// Emit `cargo:rerun-if-changed` for config file, and `cargo:rerun-if-env-changed` for override variable
+    pub fn emit_rerun_hints(&self) {
+        println!("cargo:rerun-if-changed={}", self.path.display());
+        if let Some(key) = &self.override_env {
+            println!("cargo:rerun-if-env-changed={}", key);
+        }
+    }
+}
+
+impl FeatureSource for ConfigFileFeatureSource {
+    fn is_enabled(&self, feature: &str) -> bool {
+        self.features.iter().any(|enabled| enabled == feature)
+    }
+
+    fn enabled_features(&self, _candidates: &[&str]) -> Vec<String> {
+        let mut enabled = self.features.clone();
+        enabled.sort();
+        enabled.dedup();
+        enabled
+    }
+}
+
+/// Features enabled by comma-separated environment variable(e.g. `MYCRATE_FEATURES=a,b,c`)
+#[derive(Debug, Clone)]
+pub struct ListEnvFeatureSource {
+    key: String,
+    features: Vec<String>,
+}
+
+impl ListEnvFeatureSource {
+    /// Read variable `key`. Unset variable is considered as empty list.
+    pub fn new(key: &str) -> Result<Self, Error> {
+        Ok(Self {
+            key: key.to_string(),
+            features: crate::env::var(key)?
+                .map(|list| split_list(&list))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Emit `cargo:rerun-if-env-changed` for variable
+    pub fn emit_rerun_hints(&self) {
+        println!("cargo:rerun-if-env-changed={}", self.key);
+    }
+}
+
+/// Edit distance between two names
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Most similar name among `known`, if similar enough
+fn suggest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= (name.len() / 3).max(1))
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+impl FeatureSource for ListEnvFeatureSource {
+    fn is_enabled(&self, feature: &str) -> bool {
+        self.features.iter().any(|enabled| enabled == feature)
+    }
+
+    fn enabled_features(&self, _candidates: &[&str]) -> Vec<String> {
+        let mut enabled = self.features.clone();
+        enabled.sort();
+        enabled.dedup();
+        enabled
+    }
+
+    /// Every listed feature should be generated. Similar name is suggested for typo.
+    fn validate(&self, generated: &[&str]) -> Result<(), Error> {
+        match self
+            .features
+            .iter()
+            .find(|feature| !generated.contains(&feature.as_str()))
+        {
+            Some(feature) => Err(Error::UnknownSelectedFeature {
+                name: feature.clone(),
+                suggestion: suggest(feature, generated).map(str::to_string),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestion() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(suggest("tokioo", &["tokio", "async-std"]), Some("tokio"));
+        assert_eq!(suggest("smol", &["tokio", "async-std"]), None);
+    }
+
+    #[test]
+    fn cfg_flags() {
+        let flags = [
+            "--cfg",
+            "backend_gl",
+            "--cfg=backend=\"vulkan\"",
+            "-Copt-level=3",
+        ];
+        let source = CfgFeatureSource::from_flags(flags.into_iter());
+        assert!(source.is_enabled("backend-gl"));
+        assert!(!source.is_enabled("vulkan"));
+        assert_eq!(
+            source.enabled_features(&["backend-gl", "backend-vk"]),
+            ["backend-gl"]
+        );
+
+        let source = source.with_key("backend");
+        assert!(source.is_enabled("vulkan"));
+        assert!(!source.is_enabled("backend-gl"));
+    }
+}
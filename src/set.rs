@@ -0,0 +1,90 @@
+use crate::{manifest::Named, ToFeatureName};
+
+/// Enabled features returned by add calls, with their names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureSet<T> {
+    features: Vec<(String, T)>,
+}
+
+impl<T> Default for FeatureSet<T> {
+    fn default() -> Self {
+        Self {
+            features: Vec::new(),
+        }
+    }
+}
+
+impl<T> FeatureSet<T> {
+    pub(crate) fn from_named(features: Vec<Named<T>>) -> Self {
+        Self {
+            features: features
+                .into_iter()
+                .map(|Named(name, feature)| (name, feature))
+                .collect(),
+        }
+    }
+
+    /// Whether `feature` is enabled
+    pub fn contains(&self, feature: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|enabled| enabled == feature)
+    }
+
+    /// Whether feature named `name` is enabled
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.names().any(|enabled| enabled == name)
+    }
+
+    /// Iterate enabled features
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.features.iter().map(|(_, feature)| feature)
+    }
+
+    /// Iterate names of enabled features
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Number of enabled features
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Whether none of features is enabled
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Convert into raw list of enabled features
+    pub fn into_vec(self) -> Vec<T> {
+        self.into_iter().collect()
+    }
+}
+
+impl<T: ToFeatureName> FromIterator<T> for FeatureSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(features: I) -> Self {
+        Self {
+            features: features
+                .into_iter()
+                .map(|feature| (feature.to_feature_name(), feature))
+                .collect(),
+        }
+    }
+}
+
+impl<T> IntoIterator for FeatureSet<T> {
+    type Item = T;
+    type IntoIter = std::iter::Map<std::vec::IntoIter<(String, T)>, fn((String, T)) -> T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.features.into_iter().map(|(_, feature)| feature)
+    }
+}
+
+impl<T> From<FeatureSet<T>> for Vec<T> {
+    fn from(features: FeatureSet<T>) -> Self {
+        features.into_vec()
+    }
+}
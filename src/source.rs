@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::ImDocument;
+
+use crate::{Error, FeatureName, IntoFeatureName, ItemKind, SourceLocation, ToFeatureName};
+
+/// Feature derived from filesystem entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathFeature {
+    /// Feature name made by formatter
+    pub name: FeatureName,
+    /// Scanned entry
+    pub path: PathBuf,
+}
+
+impl ToFeatureName for PathFeature {
+    fn to_feature_name(&self) -> String {
+        self.name.to_string()
+    }
+}
+
+/// Match `name` against wildcard `pattern`. `*` matches any sequence and `?` matches any character.
+pub(crate) fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (
+        pattern.chars().collect::<Vec<_>>(),
+        name.chars().collect::<Vec<_>>(),
+    );
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn scan<R: IntoFeatureName, N: Fn(&str) -> R>(
+    dir: &Path,
+    pattern: &str,
+    formatter: N,
+    directory: bool,
+) -> Result<Vec<PathFeature>, Error> {
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut features = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() != directory {
+            continue;
+        }
+        if let Some(name) = entry.file_name().to_str() {
+            if matches_wildcard(pattern, name) {
+                features.push(PathFeature {
+                    name: formatter(name).into_feature_name()?,
+                    path: entry.path(),
+                });
+            }
+        }
+    }
+    features.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(features)
+}
+
+/// Derive a feature per subdirectory of `dir` whose name matches wildcard `pattern`(e.g. `*`).\
+/// Directory name is fed through `formatter`, and `cargo:rerun-if-changed` is emitted for `dir`.
+pub fn scan_directories<R: IntoFeatureName, N: Fn(&str) -> R>(
+    dir: &Path,
+    pattern: &str,
+    formatter: N,
+) -> Result<Vec<PathFeature>, Error> {
+    scan(dir, pattern, formatter, true)
+}
+
+/// Derive a feature per file of `dir` whose name matches wildcard `pattern`(e.g. `*.ftl`).\
+/// File name is fed through `formatter`, and `cargo:rerun-if-changed` is emitted for `dir`.
+pub fn scan_files<R: IntoFeatureName, N: Fn(&str) -> R>(
+    dir: &Path,
+    pattern: &str,
+    formatter: N,
+) -> Result<Vec<PathFeature>, Error> {
+    scan(dir, pattern, formatter, false)
+}
+
+/// Strip the last extension of file name. (`en-US.ftl` -> `en-US`)
+pub fn strip_extension(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(0) | None => name,
+        Some(index) => &name[..index],
+    }
+}
+
+/// Replace characters not allowed in feature name with `-`.\
+/// Leading characters other than alphanumerics and `_` are removed.
+pub fn sanitize_feature_name(name: &str) -> String {
+    FeatureName::sanitize(name)
+        .map(FeatureName::into_string)
+        .unwrap_or_default()
+}
+
+/// Feature defined in external definition file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureDefinition {
+    pub name: String,
+    /// Dependencies in the same format of cargo manifest(`feature`, `crate/feature`, `crate?/feature`)
+    pub dependencies: Vec<String>,
+}
+
+impl ToFeatureName for FeatureDefinition {
+    fn to_feature_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Load feature definitions from TOML file, and emit `cargo:rerun-if-changed` for it.
+///
+/// File has the same shape as `[features]` table of cargo manifest. Only TOML is supported.
+/// ```toml
+/// board-a = []
+/// board-b = ["embassy/nrf52840"]
+/// ```
+pub fn load_feature_definitions(path: &Path) -> Result<Vec<FeatureDefinition>, Error> {
+    println!("cargo:rerun-if-changed={}", path.display());
+    read_feature_definitions(path)
+}
+
+/// Same as `load_feature_definitions`, without cargo directive. Useful outside build scripts.
+pub fn read_feature_definitions(path: &Path) -> Result<Vec<FeatureDefinition>, Error> {
+    let source = std::fs::read_to_string(path).map_err(|e| Error::from(e).at_manifest(path))?;
+    let document = ImDocument::parse(source.as_str())
+        .map_err(|e| Error::from(e).parsing_manifest(path, &source))?;
+    document
+        .as_table()
+        .iter()
+        .map(|(name, dependencies)| {
+            let array = dependencies.as_array().ok_or_else(|| {
+                Error::malformed(
+                    path,
+                    &[name],
+                    ItemKind::Array,
+                    ItemKind::of_item(dependencies),
+                    SourceLocation::locate(Some(&source), dependencies.span()),
+                )
+            })?;
+            let dependencies = array
+                .iter()
+                .enumerate()
+                .map(|(index, dependency)| {
+                    dependency.as_str().map(str::to_string).ok_or_else(|| {
+                        Error::malformed(
+                            path,
+                            &[name, &index.to_string()],
+                            ItemKind::String,
+                            ItemKind::of_value(dependency),
+                            SourceLocation::locate(Some(&source), dependency.span()),
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(FeatureDefinition {
+                name: name.to_string(),
+                dependencies,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard() {
+        assert!(matches_wildcard("*", ""));
+        assert!(matches_wildcard("*.ftl", "en-US.ftl"));
+        assert!(matches_wildcard("a*b*c", "aXbYbZc"));
+        assert!(matches_wildcard("board-?", "board-a"));
+        assert!(!matches_wildcard("board-?", "board-ab"));
+        assert!(!matches_wildcard("*.ftl", "en-US.ftl.bak"));
+    }
+
+    #[test]
+    fn extension() {
+        assert_eq!(strip_extension("en-US.ftl"), "en-US");
+        assert_eq!(strip_extension(".hidden"), ".hidden");
+        assert_eq!(strip_extension("README"), "README");
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    ffi::OsString,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    time::UNIX_EPOCH,
+};
+
+use crate::{state::FnvHasher, Error, ToFeatureName};
+
+const STAMP_FILE_NAME: &str = concat!(env!("CARGO_CRATE_NAME"), ".stamp");
+const FEATURE_ENV_PREFIX: &str = "CARGO_FEATURE_";
+
+/// Fingerprint of generation inputs.
+///
+/// Stamp covers added inputs(feature names, etc.), enabled features of current build and
+/// modification time of the manifest. Stamp is hashed with FNV-1a, so it is kept across toolchain updates.\
+/// When stored stamp matches, loading and writing manifest can be skipped entirely.
+pub struct GenerationStamp {
+    path: PathBuf,
+    manifest_path: PathBuf,
+    hasher: FnvHasher,
+}
+
+impl GenerationStamp {
+    /// Create stamp stored at `path` for manifest at `manifest_path`
+    pub fn new(path: PathBuf, manifest_path: PathBuf) -> Self {
+        Self {
+            path,
+            manifest_path,
+            hasher: Default::default(),
+        }
+    }
+
+    /// Create stamp stored in `OUT_DIR` for manifest of current crate
+    pub fn new_with_env() -> Result<Self, Error> {
+        let mut path = crate::env::var_path("OUT_DIR")?;
+        path.push(STAMP_FILE_NAME);
+        Ok(Self::new(path, crate::env::manifest_path()?))
+    }
+
+    /// Add names of features to be generated into stamp
+    pub fn add_features<'a, T: ToFeatureName + 'a, I: Iterator<Item = &'a T>>(
+        &mut self,
+        features: I,
+    ) {
+        for feature in features {
+            feature.to_feature_name().hash(&mut self.hasher);
+        }
+    }
+
+    /// Add any other input affecting generation into stamp
+    pub fn add_input<H: Hash + ?Sized>(&mut self, input: &H) {
+        input.hash(&mut self.hasher);
+    }
+
+    fn digest(&self) -> Result<u64, Error> {
+        let mut hasher = self.hasher;
+
+        let metadata = std::fs::metadata(&self.manifest_path)
+            .map_err(|e| Error::from(e).at_manifest(&self.manifest_path))?;
+        metadata.len().hash(&mut hasher);
+        if let Ok(modified) = metadata.modified() {
+            modified
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+
+        let mut enabled_features = std::env::vars_os()
+            .filter(|(key, _)| {
+                key.to_str()
+                    .map(|key| key.starts_with(FEATURE_ENV_PREFIX))
+                    .unwrap_or_default()
+            })
+            .map(|(key, _)| key)
+            .collect::<Vec<OsString>>();
+        enabled_features.sort();
+        enabled_features.hash(&mut hasher);
+
+        Ok(hasher.finish())
+    }
+
+    /// Check whether stored stamp matches current inputs
+    pub fn is_fresh(&self) -> Result<bool, Error> {
+        let stored = match std::fs::read_to_string(&self.path) {
+            Ok(stored) => stored,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(u64::from_str_radix(stored.trim(), 16).ok() == Some(self.digest()?))
+    }
+
+    /// Store stamp of current inputs.\
+    /// Call this after writing manifest, because the modification time of manifest is part of stamp.
+    pub fn store(&self) -> Result<(), Error> {
+        std::fs::write(&self.path, format!("{:016x}", self.digest()?))?;
+        Ok(())
+    }
+}
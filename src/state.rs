@@ -0,0 +1,315 @@
+use std::{collections::BTreeMap, path::Path};
+
+use toml_edit::{Array, DocumentMut, Item, Table, TableLike, Value};
+
+use crate::{Error, ItemKind};
+
+const PACKAGE_TABLE_NAME: &str = "package";
+const METADATA_TABLE_NAME: &str = "metadata";
+const STATE_TABLE_NAME: &str = env!("CARGO_PKG_NAME");
+const GROUPS_KEY: &str = "groups";
+const FEATURES_KEY: &str = "features";
+const HASH_KEY: &str = "hash";
+const DEPENDENCIES_KEY: &str = "dependencies";
+const OPTIONAL_DEPENDENCIES_KEY: &str = "optional-dependencies";
+const VERSION_KEY: &str = "version";
+/// Version of recorded state format. Bump with migration in `GenerationState::migrate` when format changes.\
+/// Recorded hash is 64-bit FNV-1a(`fnv1a`) of sorted features and their dependencies, stable across toolchains.
+/// Changing hashed content or algorithm also requires bump.
+const SCHEMA_VERSION: i64 = 4;
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a of `bytes`, continued from `hash`
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// `Hasher` of `fnv1a`, for fingerprints which should not change with toolchain(e.g. `GenerationStamp`)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl std::hash::Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = fnv1a(self.0, bytes);
+    }
+}
+
+/// Generation state recorded under `[package.metadata.manifest-feature-gen]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationState {
+    /// Members of each named group
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Generated features
+    pub features: Vec<String>,
+    /// Hash of generated features and their dependencies. Empty when recorded by older format.
+    pub hash: String,
+    /// Dependencies generated for each feature, before merged with existing ones
+    pub dependencies: BTreeMap<String, Vec<String>>,
+    /// Dependencies made optional by this crate
+    pub optional_dependencies: Vec<String>,
+}
+
+fn string_array(path: &Path, key: &[&str], item: &Item) -> Result<Vec<String>, Error> {
+    let array = item.as_array().ok_or_else(|| {
+        Error::malformed(path, key, ItemKind::Array, ItemKind::of_item(item), None)
+    })?;
+    array
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            value.as_str().map(str::to_string).ok_or_else(|| {
+                let index = index.to_string();
+                let key = key
+                    .iter()
+                    .copied()
+                    .chain([index.as_str()])
+                    .collect::<Vec<_>>();
+                Error::malformed(
+                    path,
+                    &key,
+                    ItemKind::String,
+                    ItemKind::of_value(value),
+                    None,
+                )
+            })
+        })
+        .collect()
+}
+
+impl GenerationState {
+    /// Hash of generated features and their dependencies, regardless of order
+    pub(crate) fn hash_of<'a, I: Iterator<Item = (&'a str, &'a [String])>>(features: I) -> String {
+        let mut features = features
+            .map(|(feature, deps)| {
+                let mut deps = deps.to_vec();
+                deps.sort();
+                (feature, deps)
+            })
+            .collect::<Vec<_>>();
+        features.sort();
+
+        // length prefixed, so that boundaries of names are unambiguous
+        let hash = features
+            .iter()
+            .fold(FNV_OFFSET_BASIS, |hash, (feature, deps)| {
+                let hash = fnv1a(hash, &(feature.len() as u64).to_le_bytes());
+                let hash = fnv1a(hash, feature.as_bytes());
+                let hash = fnv1a(hash, &(deps.len() as u64).to_le_bytes());
+                deps.iter().fold(hash, |hash, dep| {
+                    let hash = fnv1a(hash, &(dep.len() as u64).to_le_bytes());
+                    fnv1a(hash, dep.as_bytes())
+                })
+            });
+        format!("{:016x}", hash)
+    }
+
+    pub(crate) fn read(path: &Path, document: &DocumentMut) -> Result<Option<Self>, Error> {
+        let key = [PACKAGE_TABLE_NAME, METADATA_TABLE_NAME, STATE_TABLE_NAME];
+        let Some(state) = document
+            .get(PACKAGE_TABLE_NAME)
+            .and_then(|package| package.get(METADATA_TABLE_NAME))
+            .and_then(|metadata| metadata.get(STATE_TABLE_NAME))
+        else {
+            return Ok(None);
+        };
+        let state = state.as_table_like().ok_or_else(|| {
+            Error::malformed(path, &key, ItemKind::Table, ItemKind::of_item(state), None)
+        })?;
+        let version = match state.get(VERSION_KEY) {
+            Some(version) => version.as_integer().ok_or_else(|| {
+                Error::malformed(
+                    path,
+                    &[&key[..], &[VERSION_KEY]].concat(),
+                    ItemKind::Integer,
+                    ItemKind::of_item(version),
+                    None,
+                )
+            })?,
+            // recorded before versioning
+            None => 0,
+        };
+        let state = Self::migrate(version, state)?;
+
+        let features = match state.get(FEATURES_KEY) {
+            Some(features) => string_array(path, &[&key[..], &[FEATURES_KEY]].concat(), features)?,
+            None => Vec::new(),
+        };
+        let hash = match state.get(HASH_KEY) {
+            Some(hash) => hash
+                .as_str()
+                .ok_or_else(|| {
+                    Error::malformed(
+                        path,
+                        &[&key[..], &[HASH_KEY]].concat(),
+                        ItemKind::String,
+                        ItemKind::of_item(hash),
+                        None,
+                    )
+                })?
+                .to_string(),
+            None => String::new(),
+        };
+        let optional_dependencies = match state.get(OPTIONAL_DEPENDENCIES_KEY) {
+            Some(optional_dependencies) => string_array(
+                path,
+                &[&key[..], &[OPTIONAL_DEPENDENCIES_KEY]].concat(),
+                optional_dependencies,
+            )?,
+            None => Vec::new(),
+        };
+        let mut groups = BTreeMap::new();
+        if let Some(item) = state.get(GROUPS_KEY) {
+            let table = item.as_table_like().ok_or_else(|| {
+                Error::malformed(
+                    path,
+                    &[&key[..], &[GROUPS_KEY]].concat(),
+                    ItemKind::Table,
+                    ItemKind::of_item(item),
+                    None,
+                )
+            })?;
+            for (group, members) in table.iter() {
+                let members =
+                    string_array(path, &[&key[..], &[GROUPS_KEY, group]].concat(), members)?;
+                groups.insert(group.to_string(), members);
+            }
+        }
+        let mut dependencies = BTreeMap::new();
+        if let Some(item) = state.get(DEPENDENCIES_KEY) {
+            let table = item.as_table_like().ok_or_else(|| {
+                Error::malformed(
+                    path,
+                    &[&key[..], &[DEPENDENCIES_KEY]].concat(),
+                    ItemKind::Table,
+                    ItemKind::of_item(item),
+                    None,
+                )
+            })?;
+            for (feature, deps) in table.iter() {
+                let deps = string_array(
+                    path,
+                    &[&key[..], &[DEPENDENCIES_KEY, feature]].concat(),
+                    deps,
+                )?;
+                dependencies.insert(feature.to_string(), deps);
+            }
+        }
+
+        Ok(Some(Self {
+            groups,
+            features,
+            hash,
+            dependencies,
+            optional_dependencies,
+        }))
+    }
+
+    /// Bring recorded state of older format up to date
+    fn migrate(version: i64, state: &dyn TableLike) -> Result<Table, Error> {
+        if !(0..=SCHEMA_VERSION).contains(&version) {
+            return Err(Error::UnsupportedStateVersion(version));
+        }
+
+        let mut migrated = state
+            .iter()
+            .map(|(key, item)| (key, item.clone()))
+            .collect::<Table>();
+        // 0 → 1: only `version` is added
+        // 1 → 2: hash of toolchain dependent `DefaultHasher` is dropped, as it cannot be compared
+        if version < 2 {
+            migrated.remove(HASH_KEY);
+        }
+        // 2 → 3: only `dependencies` is added
+        // 3 → 4: only `optional-dependencies` is added
+        migrated.insert(VERSION_KEY, Item::Value(Value::from(SCHEMA_VERSION)));
+        Ok(migrated)
+    }
+
+    /// Remove recorded state from `document`. Emptied metadata table is also removed.
+    pub(crate) fn strip(document: &mut DocumentMut) {
+        let Some(package) = document
+            .get_mut(PACKAGE_TABLE_NAME)
+            .and_then(Item::as_table_like_mut)
+        else {
+            return;
+        };
+        let Some(metadata) = package
+            .get_mut(METADATA_TABLE_NAME)
+            .and_then(Item::as_table_like_mut)
+        else {
+            return;
+        };
+        metadata.remove(STATE_TABLE_NAME);
+        if metadata.is_empty() {
+            package.remove(METADATA_TABLE_NAME);
+        }
+    }
+
+    pub(crate) fn write(&self, document: &mut DocumentMut) {
+        let package = document
+            .entry(PACKAGE_TABLE_NAME)
+            .or_insert_with(|| Item::Table(Table::new()));
+        let Some(package) = package.as_table_like_mut() else {
+            return;
+        };
+        let metadata = package.entry(METADATA_TABLE_NAME).or_insert_with(|| {
+            let mut metadata = Table::new();
+            metadata.set_implicit(true);
+            Item::Table(metadata)
+        });
+        let Some(metadata) = metadata.as_table_like_mut() else {
+            return;
+        };
+
+        let mut groups = Table::new();
+        for (group, members) in &self.groups {
+            groups.insert(group, Item::Value(Value::Array(Array::from_iter(members))));
+        }
+        let mut state = Table::new();
+        state.insert(VERSION_KEY, Item::Value(Value::from(SCHEMA_VERSION)));
+        state.insert(
+            FEATURES_KEY,
+            Item::Value(Value::Array(Array::from_iter(&self.features))),
+        );
+        state.insert(HASH_KEY, Item::Value(Value::from(self.hash.as_str())));
+        state.insert(
+            OPTIONAL_DEPENDENCIES_KEY,
+            Item::Value(Value::Array(Array::from_iter(&self.optional_dependencies))),
+        );
+        state.insert(GROUPS_KEY, Item::Table(groups));
+        let mut dependencies = Table::new();
+        for (feature, deps) in &self.dependencies {
+            dependencies.insert(feature, Item::Value(Value::Array(Array::from_iter(deps))));
+        }
+        state.insert(DEPENDENCIES_KEY, Item::Table(dependencies));
+        metadata.insert(STATE_TABLE_NAME, Item::Table(state));
+    }
+
+    /// Replace recorded hash with `mask`, when state is recorded
+    #[cfg(feature = "testing")]
+    pub(crate) fn mask_hash(document: &mut DocumentMut, mask: &str) {
+        let hash = [METADATA_TABLE_NAME, STATE_TABLE_NAME, HASH_KEY]
+            .into_iter()
+            .try_fold(document.get_mut(PACKAGE_TABLE_NAME), |item, key| {
+                Some(item?.as_table_like_mut()?.get_mut(key))
+            })
+            .flatten();
+        if let Some(hash) = hash {
+            *hash = Item::Value(Value::from(mask));
+        }
+    }
+}
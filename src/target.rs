@@ -0,0 +1,127 @@
+use crate::{Error, Manifest};
+
+/// Rule matching target of current build
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum TargetRule {
+    /// `CARGO_CFG_TARGET_OS`(e.g. `macos`)
+    Os(String),
+    /// `CARGO_CFG_TARGET_FAMILY`(e.g. `wasm`)
+    Family(String),
+    /// `TARGET` triple(e.g. `aarch64-apple-ios`)
+    Triple(String),
+}
+
+impl TargetRule {
+    fn matches(&self) -> Result<bool, Error> {
+        let (key, expected) = match self {
+            TargetRule::Os(os) => ("CARGO_CFG_TARGET_OS", os),
+            TargetRule::Family(family) => ("CARGO_CFG_TARGET_FAMILY", family),
+            TargetRule::Triple(triple) => ("TARGET", triple),
+        };
+        Ok(crate::env::var(key)?
+            .is_some_and(|value| value.split(',').any(|value| value == expected)))
+    }
+}
+
+/// Default member of exclusion group chosen by target, used when no member is enabled.\
+/// Rules are checked in added order.
+///
+/// ```
+/// # use manifest_feature_gen::TargetDefault;
+/// let default = TargetDefault::new()
+///     .target_os("macos", "metal")
+///     .target_os("ios", "metal")
+///     .target_family("wasm", "webgl")
+///     .otherwise("vulkan");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetDefault {
+    rules: Vec<(TargetRule, String)>,
+    otherwise: Option<String>,
+}
+
+impl TargetDefault {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Choose `feature` when `CARGO_CFG_TARGET_OS` is `os`
+    pub fn target_os(mut self, os: &str, feature: &str) -> Self {
+        self.rules
+            .push((TargetRule::Os(os.to_string()), feature.to_string()));
+        self
+    }
+
+    /// Choose `feature` when `CARGO_CFG_TARGET_FAMILY` contains `family`
+    pub fn target_family(mut self, family: &str, feature: &str) -> Self {
+        self.rules
+            .push((TargetRule::Family(family.to_string()), feature.to_string()));
+        self
+    }
+
+    /// Choose `feature` when target triple(`TARGET`) is `triple`
+    pub fn target(mut self, triple: &str, feature: &str) -> Self {
+        self.rules
+            .push((TargetRule::Triple(triple.to_string()), feature.to_string()));
+        self
+    }
+
+    /// Choose `feature` when no rule matches
+    pub fn otherwise(mut self, feature: &str) -> Self {
+        self.otherwise = Some(feature.to_string());
+        self
+    }
+
+    /// Feature chosen for target of current build
+    pub fn resolve(&self) -> Result<Option<&str>, Error> {
+        for (rule, feature) in &self.rules {
+            if rule.matches()? {
+                return Ok(Some(feature));
+            }
+        }
+        Ok(self.otherwise.as_deref())
+    }
+}
+
+impl Manifest {
+    /// Choose default member of exclusion group by target, when no member is enabled.\
+    /// Features of `default` are named without prefix of the group, same as add calls.
+    pub fn set_target_default(&mut self, group: &str, default: TargetDefault) -> Result<(), Error> {
+        self.exclusion_groups
+            .get_mut(group)
+            .ok_or_else(|| Error::UnknownGroup(group.to_string()))?
+            .target_default = Some(default);
+        Ok(())
+    }
+
+    /// Member of exclusion group chosen by target, when no member is enabled
+    pub(crate) fn target_default_of(&self, group: &str) -> Result<Option<String>, Error> {
+        let Some(exclusion_group) = self.exclusion_groups.get(group) else {
+            return Ok(None);
+        };
+        if !exclusion_group.enabled.is_empty() {
+            return Ok(None);
+        }
+        let Some(target_default) = &exclusion_group.target_default else {
+            return Ok(None);
+        };
+        Ok(target_default
+            .resolve()?
+            .map(|feature| format!("{}{}", exclusion_group.prefix, feature)))
+    }
+
+    /// Member of exclusion group selected for current build.\
+    /// Default chosen by target is used when no member is enabled.
+    pub fn selected_in_group(&self, group: &str) -> Result<Option<String>, Error> {
+        let exclusion_group = self
+            .exclusion_groups
+            .get(group)
+            .ok_or_else(|| Error::UnknownGroup(group.to_string()))?;
+        match exclusion_group.enabled.first() {
+            Some(enabled) => Ok(Some(enabled.clone())),
+            None => self.target_default_of(group),
+        }
+    }
+}
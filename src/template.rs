@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use crate::{Error, Manifest};
+
+const TEMPLATE_FILE_NAME: &str = "Cargo.toml.in";
+
+impl Manifest {
+    /// Load manifest template(e.g. `Cargo.toml.in`), whose generated result is written to `path`.\
+    /// Template is never modified. Written manifest is compared with generated result as a whole,
+    /// so manual edits of written manifest are overwritten. Errors of loading refer to template.
+    pub fn new_from_template(
+        template_path: PathBuf,
+        path: PathBuf,
+        prevent_build_when_changed: bool,
+    ) -> Result<Self, Error> {
+        let source = std::fs::read_to_string(&template_path)
+            .map_err(|e| Error::from(e).at_manifest(&template_path))?;
+        let mut manifest =
+            Self::from_source_at(path, &template_path, &source, prevent_build_when_changed)?;
+        manifest.template_path = Some(template_path);
+        Ok(manifest)
+    }
+
+    /// Load `Cargo.toml.in` next to manifest of current crate, whose generated result is written to `Cargo.toml`.\
+    /// `cargo:rerun-if-changed` is emitted for template.
+    pub fn new_from_template_with_env(prevent_build_when_changed: bool) -> Result<Self, Error> {
+        let path = crate::env::manifest_path()?;
+        let template_path = path.with_file_name(TEMPLATE_FILE_NAME);
+        println!("cargo:rerun-if-changed={}", template_path.display());
+        Self::new_from_template(template_path, path, prevent_build_when_changed)
+    }
+
+    /// Path of template, if manifest is loaded from template
+    pub fn template_path(&self) -> Option<&std::path::Path> {
+        self.template_path.as_deref()
+    }
+}
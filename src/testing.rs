@@ -0,0 +1,351 @@
+//! Helpers for pinning generated features with snapshot tests, crate fixtures for integration tests
+//! and checks of feature matrix(`MatrixCheck`)
+//!
+//! ```
+//! use manifest_feature_gen::{testing, FeatureDefinition};
+//!
+//! let snapshot = testing::snapshot(
+//!     "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+//!     |manifest| {
+//!         manifest.add_feature_definitions(vec![
+//!             FeatureDefinition {
+//!                 name: "b".to_string(),
+//!                 dependencies: vec!["serde/std".to_string(), "a".to_string()],
+//!             },
+//!             FeatureDefinition {
+//!                 name: "a".to_string(),
+//!                 dependencies: vec![],
+//!             },
+//!         ])?;
+//!         Ok(())
+//!     },
+//! )
+//! .unwrap();
+//! assert!(snapshot.ends_with("[features]\na = [] # auto-generated by manifest_feature_gen\nb = [\"a\", \"serde/std\"] # auto-generated by manifest_feature_gen\n"));
+//! ```
+//!
+//! Weak dependency keeps crate name intact.
+//! ```
+//! use manifest_feature_gen::{testing, FeatureDefinition};
+//!
+//! let snapshot = testing::snapshot(
+//!     "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1\", optional = true }\n",
+//!     |manifest| {
+//!         manifest.add_feature_definitions(vec![FeatureDefinition {
+//!             name: "std".to_string(),
+//!             dependencies: vec!["serde?/std".to_string()],
+//!         }])?;
+//!         Ok(())
+//!     },
+//! )
+//! .unwrap();
+//! assert!(snapshot.contains("std = [\"serde?/std\"]"));
+//! ```
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+
+pub use crate::matrix::{MatrixCheck, MatrixResult, MatrixSummary};
+use crate::{manifest::FEATURES_TABLE_NAME, state::GenerationState, Error, Manifest};
+
+/// Placeholder replacing recorded hash of generation state
+pub const HASH_PLACEHOLDER: &str = "[hash]";
+const IN_MEMORY_MANIFEST_PATH: &str = "Cargo.toml";
+
+/// Load manifest from `source` without file. Writing it fails, so use `render` instead.
+pub fn manifest(source: &str) -> Result<Manifest, Error> {
+    Manifest::from_source(PathBuf::from(IN_MEMORY_MANIFEST_PATH), source, false)
+}
+
+/// Apply generation to manifest and render it, without writing.
+pub fn render(manifest: &mut Manifest) -> Result<String, Error> {
+    manifest.apply_generation()?;
+    Ok(crate::LineEnding::Lf.apply(&manifest.document.to_string()))
+}
+
+/// Normalize rendered manifest for stable snapshot.
+///
+/// Entries of `[features]` and their dependencies are sorted, recorded hash is replaced with
+/// `HASH_PLACEHOLDER` and line endings are converted to `\n`.
+pub fn normalize(rendered: &str) -> Result<String, Error> {
+    let mut document = rendered.parse::<DocumentMut>()?;
+    if let Some(features) = document
+        .get_mut(FEATURES_TABLE_NAME)
+        .and_then(Item::as_table_mut)
+    {
+        features.sort_values();
+        for (_, item) in features.iter_mut() {
+            if let Some(dependencies) = item.as_array_mut() {
+                dependencies.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                dependencies.fmt();
+            }
+        }
+    }
+    GenerationState::mask_hash(&mut document, HASH_PLACEHOLDER);
+    Ok(crate::LineEnding::Lf.apply(&document.to_string()))
+}
+
+/// Load manifest from `source`, run `generator` against it, then render normalized output
+pub fn snapshot<F: FnOnce(&mut Manifest) -> Result<(), Error>>(
+    source: &str,
+    generator: F,
+) -> Result<String, Error> {
+    let mut manifest = manifest(source)?;
+    generator(&mut manifest)?;
+    normalize(&render(&mut manifest)?)
+}
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+static FIXTURE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+const FEATURE_ENV_PREFIX: &str = "CARGO_FEATURE_";
+
+/// Builder of throwaway crate directory, for integration tests of generators.
+///
+/// ```
+/// use manifest_feature_gen::{testing::CrateFixture, Manifest};
+///
+/// let fixture = CrateFixture::new("foo")
+///     .path_dependency("bar", "[features]\nstd = []\n")
+///     .features(["bar"])
+///     .build()
+///     .unwrap();
+/// let enabled = fixture
+///     .run(|| {
+///         let manifest = Manifest::new_with_env(false)?;
+///         Ok(manifest.enabled_features())
+///     })
+///     .unwrap();
+/// assert_eq!(enabled, ["bar"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrateFixture {
+    name: String,
+    manifest: Option<String>,
+    path_dependencies: Vec<(String, String)>,
+    files: Vec<(PathBuf, String)>,
+    features: Vec<String>,
+    env: Vec<(String, String)>,
+}
+
+impl CrateFixture {
+    /// Crate named `name`, with minimal manifest
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            manifest: None,
+            path_dependencies: Vec::new(),
+            files: Vec::new(),
+            features: Vec::new(),
+            env: Vec::new(),
+        }
+    }
+
+    /// Replace whole manifest. Path dependencies are still appended to `[dependencies]`.
+    pub fn manifest(mut self, source: &str) -> Self {
+        self.manifest = Some(source.to_string());
+        self
+    }
+
+    /// Add optional path dependency `name` in subdirectory of same name.\
+    /// `manifest_tail` is appended to its minimal manifest(e.g. `[features]` table).
+    pub fn path_dependency(mut self, name: &str, manifest_tail: &str) -> Self {
+        self.path_dependencies
+            .push((name.to_string(), manifest_tail.to_string()));
+        self
+    }
+
+    /// Add file at `path` relative to crate directory
+    pub fn file<P: Into<PathBuf>>(mut self, path: P, content: &str) -> Self {
+        self.files.push((path.into(), content.to_string()));
+        self
+    }
+
+    /// Features enabled while running, as cargo sets `CARGO_FEATURE_*` for build script
+    pub fn features<'a, I: IntoIterator<Item = &'a str>>(mut self, features: I) -> Self {
+        self.features
+            .extend(features.into_iter().map(str::to_string));
+        self
+    }
+
+    /// Environment variable set while running
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    fn manifest_source(&self) -> Result<String, Error> {
+        let mut source = self.manifest.clone().unwrap_or_else(|| {
+            format!(
+                "[package]\nname = {:?}\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+                self.name
+            )
+        });
+        if !self.path_dependencies.is_empty() {
+            let mut document = source.parse::<DocumentMut>()?;
+            let dependencies = document[crate::dependencies::DEPENDENCIES_TABLE_NAME]
+                .or_insert(Item::Table(Table::new()));
+            for (name, _) in &self.path_dependencies {
+                let mut dependency = InlineTable::new();
+                dependency.insert("path", name.as_str().into());
+                dependency.insert("optional", true.into());
+                dependencies[name.as_str()] = Item::Value(Value::InlineTable(dependency));
+            }
+            source = document.to_string();
+        }
+        Ok(source)
+    }
+
+    /// Create crate directory under temporary directory.\
+    /// Fails when manifest set by `manifest` cannot be parsed.
+    pub fn build(self) -> Result<FixtureCrate, Error> {
+        let manifest_source = self.manifest_source()?;
+        let dir = std::env::temp_dir().join(format!(
+            "{}-fixture-{}-{}-{}",
+            env!("CARGO_CRATE_NAME"),
+            self.name,
+            std::process::id(),
+            FIXTURE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(dir.join("src"))?;
+        std::fs::write(dir.join("Cargo.toml"), manifest_source)?;
+        std::fs::write(dir.join("src/lib.rs"), "")?;
+        for (name, manifest_tail) in &self.path_dependencies {
+            let dependency_dir = dir.join(name);
+            std::fs::create_dir_all(dependency_dir.join("src"))?;
+            std::fs::write(
+                dependency_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = {:?}\nversion = \"0.1.0\"\nedition = \"2021\"\n\n{}",
+                    name, manifest_tail
+                ),
+            )?;
+            std::fs::write(dependency_dir.join("src/lib.rs"), "")?;
+        }
+        for (path, content) in &self.files {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+
+        let out_dir = dir.join("target").join("out");
+        std::fs::create_dir_all(&out_dir)?;
+        let mut env = vec![
+            ("CARGO_MANIFEST_DIR".to_string(), dir.display().to_string()),
+            ("OUT_DIR".to_string(), out_dir.display().to_string()),
+            ("CARGO_PKG_NAME".to_string(), self.name.clone()),
+        ];
+        env.extend(self.features.iter().map(|feature| {
+            (
+                format!(
+                    "{}{}",
+                    FEATURE_ENV_PREFIX,
+                    crate::cargo_env_mangling(feature)
+                ),
+                "1".to_string(),
+            )
+        }));
+        env.extend(self.env);
+
+        Ok(FixtureCrate { dir, env })
+    }
+}
+
+/// Environment variables restored when dropped, even when build script panics
+struct EnvGuard {
+    previous: Vec<(OsString, Option<OsString>)>,
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (key, value) in self.previous.drain(..) {
+            match value {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+    }
+}
+
+/// Crate directory created by `CrateFixture`. Directory is removed when dropped.
+#[derive(Debug)]
+pub struct FixtureCrate {
+    dir: PathBuf,
+    env: Vec<(String, String)>,
+}
+
+impl FixtureCrate {
+    /// Crate directory
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of crate manifest
+    pub fn manifest_path(&self) -> PathBuf {
+        self.dir.join("Cargo.toml")
+    }
+
+    /// Current content of crate manifest
+    pub fn manifest_source(&self) -> Result<String, Error> {
+        Ok(std::fs::read_to_string(self.manifest_path())?)
+    }
+
+    /// Run `build_script` with environment of build script of this crate(`CARGO_MANIFEST_DIR`,
+    /// `OUT_DIR`, `CARGO_FEATURE_*` and added variables).\
+    /// Environment is process-wide, so runs are serialized and previous environment is restored after run,
+    /// even when `build_script` panics.
+    ///
+    /// Modifying environment is unsound while other threads read it(`std::env::set_var`).
+    /// Runs are serialized only against each other, so avoid reading environment from other tests
+    /// running in parallel, or run tests with `--test-threads=1`.
+    pub fn run<T, F: FnOnce() -> Result<T, Error>>(&self, build_script: F) -> Result<T, Error> {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let inherited_features = std::env::vars_os()
+            .map(|(key, _)| key)
+            .filter(|key| {
+                key.to_str()
+                    .is_some_and(|key| key.starts_with(FEATURE_ENV_PREFIX))
+            })
+            .collect::<Vec<_>>();
+        let mut keys = inherited_features.clone();
+        keys.extend(self.env.iter().map(|(key, _)| OsString::from(key)));
+        keys.sort();
+        keys.dedup();
+        // restored before lock is released
+        let _guard = EnvGuard {
+            previous: keys
+                .into_iter()
+                .map(|key| {
+                    let value = std::env::var_os(&key);
+                    (key, value)
+                })
+                .collect(),
+        };
+
+        for key in &inherited_features {
+            std::env::remove_var(key);
+        }
+        for (key, value) in &self.env {
+            std::env::set_var(key, value);
+        }
+        build_script()
+    }
+}
+
+impl Drop for FixtureCrate {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use crate::{Error, GenerationReport, Manifest, WriteOptions};
+
+/// Original content of files touched while writing. `None` means file did not exist.
+pub(crate) struct Snapshot {
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl Snapshot {
+    pub(crate) fn capture<I: IntoIterator<Item = PathBuf>>(paths: I) -> Result<Self, Error> {
+        let files = paths
+            .into_iter()
+            .map(|path| match std::fs::read(&path) {
+                Ok(content) => Ok((path, Some(content))),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((path, None)),
+                Err(e) => Err(e.into()),
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { files })
+    }
+
+    pub(crate) fn restore(&self) -> Result<(), Error> {
+        for (path, content) in &self.files {
+            match content {
+                Some(content) => std::fs::write(path, content)?,
+                None if path.exists() => std::fs::remove_file(path)?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Manifest {
+    /// Write manifest, then run `side_outputs`(e.g. `update_lock`, `write_exclusion_guards_with_env`).\
+    /// When writing or `side_outputs` fails, manifest and lockfile are restored to their original content.
+    ///
+    /// ```no_run
+    /// # use manifest_feature_gen::Manifest;
+    /// let manifest = Manifest::new_with_env(true).unwrap();
+    /// manifest
+    ///     .write_transaction(&Default::default(), |manifest| {
+    ///         manifest.update_lock()?;
+    ///         manifest.write_exclusion_guards_with_env("guards.rs")?;
+    ///         Ok(())
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn write_transaction<F: FnOnce(&mut Manifest) -> Result<(), Error>>(
+        mut self,
+        options: &WriteOptions,
+        side_outputs: F,
+    ) -> Result<GenerationReport, Error> {
+        let snapshot = Snapshot::capture([self.path.clone(), self.lock_path()])?;
+
+        let result = self
+            .write_document(options)
+            .and_then(|report| side_outputs(&mut self).map(|_| report));
+        match result {
+            Ok(report) if report.written && self.prevent_build_when_changed => {
+                Err(Error::ManifestChanged)
+            }
+            Ok(report) => Ok(report),
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "write failed, manifest is restored");
+                snapshot.restore()?;
+                Err(e)
+            }
+        }
+    }
+}
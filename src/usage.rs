@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use crate::{Error, FeatureName, Manifest, SourceLocation};
+
+const SOURCE_DIR_NAME: &str = "src";
+const CFG_MACROS: [&str; 3] = ["cfg(", "cfg_attr(", "cfg!("];
+
+/// Occurrence of `feature = "..."` in `cfg`, `cfg_attr` or `cfg!` of source
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureUsage {
+    pub feature: String,
+    /// Source file containing this usage
+    pub path: PathBuf,
+    pub location: SourceLocation,
+}
+
+impl std::fmt::Display for FeatureUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: feature = \"{}\"",
+            self.path.display(),
+            self.location.line,
+            self.location.column,
+            self.feature
+        )
+    }
+}
+
+/// Whether `c` can continue identifier
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte range of content inside parentheses, starting right after `(`
+fn balanced_end(source: &str, start: usize) -> usize {
+    let mut depth = 1;
+    for (offset, c) in source[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return start + offset;
+                }
+            }
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// Replace comments with spaces, keeping byte offsets of other content
+fn blank_comments(source: &str) -> String {
+    let mut blanked = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            blanked.push(c);
+            match c {
+                '\\' => blanked.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('\'', Some('"')) => {
+                blanked.push(c);
+                blanked.extend(chars.next());
+            }
+            ('"', _) => {
+                in_string = true;
+                blanked.push(c);
+            }
+            ('/', Some('/')) => {
+                blanked.push(' ');
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    blanked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+                }
+            }
+            ('/', Some('*')) => {
+                blanked.push(' ');
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    match c {
+                        '\n' => blanked.push(c),
+                        _ => blanked.extend(std::iter::repeat_n(' ', c.len_utf8())),
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => blanked.push(c),
+        }
+    }
+    blanked
+}
+
+/// Byte offsets and names of `feature = "..."` predicates in cfg of `source`
+fn feature_predicates(source: &str) -> Vec<(usize, String)> {
+    let source = blank_comments(source);
+    let source = source.as_str();
+    let mut predicates = Vec::new();
+    let mut cursor = 0;
+    while let Some((start, cfg)) = CFG_MACROS
+        .iter()
+        .filter_map(|cfg| Some((source[cursor..].find(cfg)? + cursor, *cfg)))
+        .min_by_key(|(start, _)| *start)
+    {
+        cursor = start + cfg.len();
+        let preceded_by_ident = source[..start]
+            .chars()
+            .next_back()
+            .is_some_and(is_ident_char);
+        if preceded_by_ident {
+            continue;
+        }
+        let end = balanced_end(source, cursor);
+        let mut inner = cursor;
+        while let Some(found) = source[inner..end].find("feature") {
+            let name_start = inner + found;
+            inner = name_start + "feature".len();
+            if source[..name_start]
+                .chars()
+                .next_back()
+                .is_some_and(is_ident_char)
+            {
+                continue;
+            }
+            let rest = source[inner..end].trim_start();
+            let Some(rest) = rest.strip_prefix('=') else {
+                continue;
+            };
+            let Some(rest) = rest.trim_start().strip_prefix('"') else {
+                continue;
+            };
+            if let Some(name) = rest.split('"').next().filter(|_| rest.contains('"')) {
+                predicates.push((name_start, name.to_string()));
+            }
+        }
+        cursor = end;
+    }
+    predicates
+}
+
+fn scan_dir(dir: &Path, usages: &mut Vec<FeatureUsage>) -> Result<(), Error> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            scan_dir(&path, usages)?;
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = std::fs::read_to_string(&path)?;
+            for (offset, feature) in feature_predicates(&source) {
+                if let Some(location) =
+                    SourceLocation::from_span(&source, offset..offset + "feature".len())
+                {
+                    usages.push(FeatureUsage {
+                        feature,
+                        path: path.clone(),
+                        location,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Manifest {
+    /// Scan `src/**/*.rs` next to manifest for `feature = "..."` in `cfg`, `cfg_attr` and `cfg!`.\
+    /// Nothing is found when `src` does not exist.
+    pub fn scan_feature_usages(&self) -> Result<Vec<FeatureUsage>, Error> {
+        let dir = self
+            .path
+            .parent()
+            .unwrap_or(".".as_ref())
+            .join(SOURCE_DIR_NAME);
+        let mut usages = Vec::new();
+        if dir.is_dir() {
+            scan_dir(&dir, &mut usages)?;
+        }
+        Ok(usages)
+    }
+
+    /// Generated features never referenced in source, found by `scan_feature_usages`.\
+    /// Feature is considered used when any feature enabled by it is referenced,
+    /// or it enables dependencies or their features.
+    pub fn unused_generated_features(&self) -> Result<Vec<FeatureName>, Error> {
+        let usages = self.scan_feature_usages()?;
+        Ok(self
+            .generated_features
+            .iter()
+            .filter(|feature| {
+                let resolved = self.resolve(&[feature.as_str()]);
+                resolved.dependencies.is_empty()
+                    && resolved.crate_features.is_empty()
+                    && !usages.iter().any(|usage| resolved.contains(&usage.feature))
+            })
+            .map(|feature| FeatureName::new_unchecked(feature.clone()))
+            .collect())
+    }
+
+    /// Usages of features declared neither in `[features]`(including generated ones) nor as optional dependency.\
+    /// Typo of feature name silently disables code, as cfg of undeclared feature is never enabled.
+    pub fn unknown_feature_usages(&self) -> Result<Vec<FeatureUsage>, Error> {
+        let optional_dependencies = self
+            .dependency_entries()
+            .into_iter()
+            .filter(|(_, item)| crate::dependencies::is_optional_entry(item))
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        Ok(self
+            .scan_feature_usages()?
+            .into_iter()
+            .filter(|usage| {
+                !self.has_feature(&usage.feature)
+                    && !optional_dependencies.contains(&usage.feature.as_str())
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,463 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::{Array, DocumentMut, ImDocument, InlineTable, Item, Table, Value};
+
+use crate::{Error, ItemKind, LineEnding, Manifest};
+
+const WORKSPACE_TABLE_NAME: &str = "workspace";
+const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+/// Keys of dependency entry conflicting with `workspace = true`, as they are inherited
+const INHERITED_DEPENDENCY_KEYS: [&str; 9] = [
+    "version",
+    "path",
+    "git",
+    "branch",
+    "tag",
+    "rev",
+    "registry",
+    "registry-index",
+    "package",
+];
+
+/// Dependency declaration, with `workspace = true` resolved against `[workspace.dependencies]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencySpec {
+    /// Name of dependency in manifest
+    pub name: String,
+    /// Renamed package(`package = "..."`)
+    pub package: Option<String>,
+    pub version: Option<String>,
+    /// Path of path dependency, resolved against the manifest declaring it
+    pub path: Option<PathBuf>,
+    /// Optional dependency. Always declared in member manifest.
+    pub optional: bool,
+    /// Features enabled in member and workspace declarations
+    pub features: Vec<String>,
+    /// Whether declaration is inherited from workspace
+    pub inherited: bool,
+}
+
+impl DependencySpec {
+    fn merge(&mut self, dir: &Path, item: &Item) {
+        if let Some(version) = item.as_str() {
+            self.version = Some(version.to_string());
+            return;
+        }
+        if let Some(package) = item.get("package").and_then(Item::as_str) {
+            self.package = Some(package.to_string());
+        }
+        if let Some(version) = item.get("version").and_then(Item::as_str) {
+            self.version = Some(version.to_string());
+        }
+        if let Some(path) = item.get("path").and_then(Item::as_str) {
+            self.path = Some(dir.join(path));
+        }
+        self.features.extend(
+            item.get("features")
+                .and_then(Item::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string),
+        );
+    }
+}
+
+/// Declaration of dependency in `[workspace.dependencies]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceDependency {
+    pub version: Option<String>,
+    /// Path of path dependency, relative to workspace root
+    pub path: Option<String>,
+    /// Renamed package(`package = "..."`)
+    pub package: Option<String>,
+    pub features: Vec<String>,
+}
+
+impl WorkspaceDependency {
+    pub fn version(version: &str) -> Self {
+        Self {
+            version: Some(version.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn path(path: &str) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn into_item(self) -> Item {
+        if let (Some(version), None, None, true) = (
+            &self.version,
+            &self.path,
+            &self.package,
+            self.features.is_empty(),
+        ) {
+            return Item::Value(version.as_str().into());
+        }
+
+        let mut table = InlineTable::new();
+        if let Some(version) = self.version {
+            table.insert("version", version.into());
+        }
+        if let Some(path) = self.path {
+            table.insert("path", path.into());
+        }
+        if let Some(package) = self.package {
+            table.insert("package", package.into());
+        }
+        if !self.features.is_empty() {
+            table.insert("features", Value::Array(Array::from_iter(self.features)));
+        }
+        Item::Value(Value::InlineTable(table))
+    }
+}
+
+/// Workspace root manifest, of which `[workspace.dependencies]` can be edited
+pub struct WorkspaceManifest {
+    path: PathBuf,
+    document: DocumentMut,
+    line_ending: LineEnding,
+    changed: bool,
+}
+
+impl WorkspaceManifest {
+    /// Open workspace root manifest at `path`
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let source =
+            std::fs::read_to_string(&path).map_err(|e| Error::from(e).at_manifest(&path))?;
+        let line_ending = LineEnding::detect(&source);
+        let document = source
+            .parse::<DocumentMut>()
+            .map_err(|e| Error::from(e).at_manifest(&path))?;
+        if !document.contains_key(WORKSPACE_TABLE_NAME) {
+            return Err(Error::malformed(
+                &path,
+                &[WORKSPACE_TABLE_NAME],
+                ItemKind::Table,
+                ItemKind::None,
+                None,
+            ));
+        }
+
+        Ok(Self {
+            path,
+            document,
+            line_ending,
+            changed: false,
+        })
+    }
+
+    /// Locate workspace root manifest, which manifest at `manifest_path` belongs to.\
+    /// `package.workspace` is respected. Otherwise, ancestors are searched for `[workspace]`, including the manifest itself.
+    pub fn locate(manifest_path: &Path) -> Result<Option<PathBuf>, Error> {
+        let source = std::fs::read_to_string(manifest_path)
+            .map_err(|e| Error::from(e).at_manifest(manifest_path))?;
+        let document = ImDocument::parse(source.as_str())
+            .map_err(|e| Error::from(e).at_manifest(manifest_path))?;
+        let dir = manifest_path.parent().unwrap_or(".".as_ref());
+        if let Some(root) = document
+            .get("package")
+            .and_then(|package| package.get(WORKSPACE_TABLE_NAME))
+            .and_then(Item::as_str)
+        {
+            return Ok(Some(dir.join(root).join(MANIFEST_FILE_NAME)));
+        }
+        if document.contains_key(WORKSPACE_TABLE_NAME) {
+            return Ok(Some(manifest_path.to_path_buf()));
+        }
+
+        for ancestor in dir.ancestors().skip(1) {
+            let candidate = ancestor.join(MANIFEST_FILE_NAME);
+            let Ok(source) = std::fs::read_to_string(&candidate) else {
+                continue;
+            };
+            if ImDocument::parse(source.as_str())
+                .map_err(|e| Error::from(e).at_manifest(&candidate))?
+                .contains_key(WORKSPACE_TABLE_NAME)
+            {
+                return Ok(Some(candidate));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Locate workspace root manifest of crate being built, with `CARGO_MANIFEST_DIR`
+    pub fn locate_with_env() -> Result<Option<PathBuf>, Error> {
+        Self::locate(&crate::env::manifest_path()?)
+    }
+
+    /// Locate and open workspace root manifest of crate being built.\
+    /// Virtual workspace root is also supported.
+    pub fn new_with_env() -> Result<Self, Error> {
+        let path = crate::env::manifest_path()?;
+        let root = Self::locate(&path)?.ok_or(Error::NotInWorkspace(path))?;
+        Self::open(root)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Field of `[workspace.package]`, inherited by members with `workspace = true`
+    pub fn package_field(&self, key: &str) -> Option<&Item> {
+        self.document
+            .get(WORKSPACE_TABLE_NAME)?
+            .get("package")?
+            .get(key)
+    }
+
+    /// Whether root manifest is virtual manifest, which has no `[package]`
+    pub fn is_virtual(&self) -> bool {
+        !self.document.contains_key("package")
+    }
+
+    /// Manifest paths of workspace members.\
+    /// Members ending with `*` are expanded to subdirectories containing `Cargo.toml`. Other glob patterns are not supported.
+    pub fn member_paths(&self) -> Result<Vec<PathBuf>, Error> {
+        let root = self.path.parent().unwrap_or(".".as_ref());
+        let members = self
+            .document
+            .get(WORKSPACE_TABLE_NAME)
+            .and_then(|workspace| workspace.get("members"))
+            .and_then(Item::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str);
+
+        let mut paths = Vec::new();
+        for member in members {
+            if let Some(parent) = member.strip_suffix('*') {
+                let mut expanded = std::fs::read_dir(root.join(parent))?
+                    .filter_map(|entry| Some(entry.ok()?.path().join(MANIFEST_FILE_NAME)))
+                    .filter(|path| path.exists())
+                    .collect::<Vec<_>>();
+                expanded.sort();
+                paths.extend(expanded);
+            } else {
+                paths.push(root.join(member).join(MANIFEST_FILE_NAME));
+            }
+        }
+        if !self.is_virtual() && !paths.iter().any(|path| path == &self.path) {
+            paths.insert(0, self.path.clone());
+        }
+        Ok(paths)
+    }
+
+    /// Entry of `[workspace.dependencies]`
+    pub fn dependency(&self, name: &str) -> Option<&Item> {
+        self.document
+            .get(WORKSPACE_TABLE_NAME)?
+            .get(crate::dependencies::DEPENDENCIES_TABLE_NAME)?
+            .get(name)
+    }
+
+    /// Add or replace entry of `[workspace.dependencies]`.\
+    /// Returns whether the entry is changed.
+    pub fn set_dependency(&mut self, name: &str, dependency: WorkspaceDependency) -> bool {
+        let item = dependency.into_item();
+        if self
+            .dependency(name)
+            .map(|current| current.to_string().trim().to_string())
+            == Some(item.to_string().trim().to_string())
+        {
+            return false;
+        }
+
+        let workspace = self.document[WORKSPACE_TABLE_NAME].or_insert(Item::Table(Table::new()));
+        let dependencies = workspace[crate::dependencies::DEPENDENCIES_TABLE_NAME]
+            .or_insert(Item::Table(Table::new()));
+        dependencies[name] = item;
+        self.changed = true;
+        true
+    }
+
+    /// Write manifest when any entry is changed. Returns whether file is written.
+    pub fn write(self) -> Result<bool, Error> {
+        if !self.changed {
+            return Ok(false);
+        }
+        std::fs::write(
+            &self.path,
+            self.line_ending.apply(&self.document.to_string()),
+        )
+        .map_err(|e| Error::from(e).at_manifest(&self.path))?;
+        Ok(true)
+    }
+}
+
+impl Manifest {
+    /// Manifest path of workspace root, which this manifest belongs to
+    pub(crate) fn workspace_root_path(&self) -> Result<Option<PathBuf>, Error> {
+        WorkspaceManifest::locate(&self.path)
+    }
+
+    /// Resolve dependency declaration. `workspace = true` is resolved against root `[workspace.dependencies]`.
+    pub fn dependency_spec(&self, name: &str) -> Result<Option<DependencySpec>, Error> {
+        let Some(entry) = self.dependency_entry(name) else {
+            return Ok(None);
+        };
+        let mut spec = DependencySpec {
+            name: name.to_string(),
+            package: None,
+            version: None,
+            path: None,
+            optional: crate::dependencies::is_optional_entry(entry),
+            features: Vec::new(),
+            inherited: entry.get(WORKSPACE_TABLE_NAME).and_then(Item::as_bool) == Some(true),
+        };
+
+        if spec.inherited {
+            let root = self
+                .workspace_root_path()?
+                .ok_or_else(|| Error::UnresolvedDependency(name.to_string()))?;
+            let source =
+                std::fs::read_to_string(&root).map_err(|e| Error::from(e).at_manifest(&root))?;
+            let document = source
+                .parse::<DocumentMut>()
+                .map_err(|e| Error::from(e).at_manifest(&root))?;
+            let declared = document
+                .get(WORKSPACE_TABLE_NAME)
+                .and_then(|workspace| workspace.get(crate::dependencies::DEPENDENCIES_TABLE_NAME))
+                .and_then(|dependencies| dependencies.get(name))
+                .ok_or_else(|| Error::UnresolvedDependency(name.to_string()))?;
+            spec.merge(root.parent().unwrap_or(".".as_ref()), declared);
+        }
+        spec.merge(self.path.parent().unwrap_or(".".as_ref()), entry);
+
+        Ok(Some(spec))
+    }
+
+    /// Mark dependency as optional or not.\
+    /// For dependency inherited from workspace, `optional` is placed in member manifest, as cargo requires.
+    pub fn set_dependency_optional(&mut self, name: &str, optional: bool) -> Result<(), Error> {
+        let mut found = false;
+        for table in self.dependency_tables_mut() {
+            let Some(entry) = table.get_mut(name) else {
+                continue;
+            };
+            found = true;
+            if let Some(version) = entry.as_str().map(str::to_string) {
+                if !optional {
+                    continue;
+                }
+                let mut inline = InlineTable::new();
+                inline.insert("version", version.into());
+                *entry = Item::Value(Value::InlineTable(inline));
+            }
+            if let Some(entry) = entry.as_table_like_mut() {
+                if optional {
+                    entry.insert("optional", Item::Value(true.into()));
+                } else {
+                    entry.remove("optional");
+                }
+            }
+            if let Some(entry) = entry.as_inline_table_mut() {
+                entry.fmt();
+            }
+        }
+
+        if found {
+            if optional {
+                self.optional_dependencies.insert(name.to_string());
+            } else {
+                self.optional_dependencies.remove(name);
+            }
+            Ok(())
+        } else {
+            Err(Error::UnknownDependency(name.to_string()))
+        }
+    }
+
+    /// Open root manifest of workspace, which this manifest belongs to
+    pub fn workspace_manifest(&self) -> Result<WorkspaceManifest, Error> {
+        let root = self
+            .workspace_root_path()?
+            .ok_or_else(|| Error::NotInWorkspace(self.path.clone()))?;
+        WorkspaceManifest::open(root)
+    }
+
+    /// Declare `dependency` in `[workspace.dependencies]` of `workspace`,
+    /// and refer to it from this manifest with `workspace = true`.\
+    /// Existing entry is edited in place, removing only keys inherited from workspace. So its `features`, `optional`
+    /// and comments are kept. `workspace` should be written separately.
+    pub fn add_workspace_dependency(
+        &mut self,
+        workspace: &mut WorkspaceManifest,
+        name: &str,
+        dependency: WorkspaceDependency,
+        optional: bool,
+    ) {
+        workspace.set_dependency(name, dependency);
+
+        let dependencies = self.document[crate::dependencies::DEPENDENCIES_TABLE_NAME]
+            .or_insert(Item::Table(Table::new()));
+        let entry = &mut dependencies[name];
+        if entry.as_table_like().is_none() {
+            // bare version or missing entry
+            let mut table = InlineTable::new();
+            if let Some(value) = entry.as_value() {
+                *table.decor_mut() = value.decor().clone();
+            }
+            *entry = Item::Value(Value::InlineTable(table));
+        }
+        let table = entry.as_table_like_mut().unwrap();
+        for key in INHERITED_DEPENDENCY_KEYS {
+            table.remove(key);
+        }
+        table.insert(WORKSPACE_TABLE_NAME, Item::Value(true.into()));
+        if optional {
+            table.insert("optional", Item::Value(true.into()));
+            self.optional_dependencies.insert(name.to_string());
+        }
+        if let Some(table) = entry.as_inline_table_mut() {
+            table.fmt();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_dependency_keeps_entry() {
+        let mut workspace = WorkspaceManifest {
+            path: PathBuf::from("Cargo.toml"),
+            document: "[workspace]\n".parse().unwrap(),
+            line_ending: LineEnding::Lf,
+            changed: false,
+        };
+        let mut manifest = Manifest::from_source(
+            PathBuf::from("member/Cargo.toml"),
+            r#"[package]
+name = "member"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", features = ["derive"], optional = true } # serialization
+log = "0.4" # logging
+"#,
+            false,
+        )
+        .unwrap();
+
+        for name in ["serde", "log"] {
+            manifest.add_workspace_dependency(
+                &mut workspace,
+                name,
+                WorkspaceDependency::version("1"),
+                false,
+            );
+        }
+        let rendered = manifest.document().to_string();
+        assert!(rendered.contains(
+            "serde = { features = [\"derive\"], optional = true, workspace = true } # serialization"
+        ));
+        assert!(rendered.contains("log = { workspace = true } # logging"));
+        assert!(!manifest.optional_dependencies.contains("serde"));
+    }
+}